@@ -0,0 +1,87 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::{collections::HashSet, io::Cursor};
+
+use test::Bencher;
+use y2022::day03::*;
+
+const LINES: usize = 3000;
+const HALF: usize = 16;
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn input() -> String {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        ITEMS[(state % ITEMS.len() as u64) as usize] as char
+    };
+    (0..LINES).map(|_| (0..2 * HALF).map(|_| next()).chain(['\n']).collect::<String>()).collect()
+}
+
+fn priority(c: &char) -> u32 {
+    Priority::new(*c).unwrap().into_inner()
+}
+
+// The previous `HashSet` based implementation, kept as a reference.
+fn common_sum_hash_set(input: &str) -> u32 {
+    let halves = input.lines().map(|line| line.split_at(line.len() / 2));
+    let sets = halves.map(|(c0, c1)| (c0.chars().collect(), c1.chars().collect()));
+    sets.map(|(c0, c1): (HashSet<char>, HashSet<char>)| {
+        c0.intersection(&c1).map(priority).sum::<u32>()
+    })
+    .sum()
+}
+
+fn badges_sum_hash_set(input: &str) -> u32 {
+    let lines = input.lines().collect::<Vec<_>>();
+    let groups = lines.chunks(3).map(|group| {
+        let mut sets = group.iter().map(|line| line.chars().collect::<HashSet<_>>());
+        let first = sets.next().unwrap();
+        sets.fold(first, |acc, set| &acc & &set)
+    });
+    groups.map(|badges| badges.iter().map(priority).sum::<u32>()).sum()
+}
+
+fn common_sum_item_set(input: &str) -> u32 {
+    RucksackReader::new(Cursor::new(input)).common_sum().unwrap()
+}
+
+// Random groups may have any number of badges, so add them all up.
+fn badges_sum_item_set(input: &str) -> u32 {
+    let rucksacks = RucksackReader::new(Cursor::new(input)).map(Result::unwrap).collect::<Vec<_>>();
+    let table = PriorityTable::default();
+    let groups = rucksacks.chunks(3).map(|group| {
+        group.iter().map(Rucksack::all).reduce(|acc, set| acc & set).unwrap_or_default()
+    });
+    groups.map(|badges| badges.priority_sum(&table)).sum()
+}
+
+#[bench]
+fn common_sum_with_hash_set(b: &mut Bencher) {
+    let input = input();
+    assert_eq!(common_sum_item_set(&input), common_sum_hash_set(&input));
+    b.iter(|| common_sum_hash_set(&input));
+}
+
+#[bench]
+fn common_sum_with_item_set(b: &mut Bencher) {
+    let input = input();
+    b.iter(|| common_sum_item_set(&input));
+}
+
+#[bench]
+fn badges_sum_with_hash_set(b: &mut Bencher) {
+    let input = input();
+    assert_eq!(badges_sum_item_set(&input), badges_sum_hash_set(&input));
+    b.iter(|| badges_sum_hash_set(&input));
+}
+
+#[bench]
+fn badges_sum_with_item_set(b: &mut Bencher) {
+    let input = input();
+    b.iter(|| badges_sum_item_set(&input));
+}
@@ -8,8 +8,8 @@ fn main() {
 
 fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     let file = day02_file()?;
-    let game = Game::new(file);
-    let stats = game.tournament1()?;
+    let game = Game::new(file, Decoding::shapes());
+    let stats = game.tournament()?;
     let player = stats.protagonist();
     let score = player.score();
     println!("{score}");
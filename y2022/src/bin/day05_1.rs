@@ -1,3 +1,5 @@
+use std::io;
+
 use y2022::day05::*;
 
 fn main() {
@@ -8,8 +10,12 @@ fn main() {
 
 fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     let file = day05_file()?;
-    let (mut platform, lifts) = drawing(file);
-    platform.try_lifts_rev(lifts)?;
+    let (platform, lifts) = drawing(file)?;
+    let mut platform = platform.with_crane(CrateMover9000);
+    match animation_delay()? {
+        Some(delay) => animate(platform.steps(lifts), io::stdout(), delay)?,
+        None => platform.try_lifts(lifts)?,
+    }
     let answer = platform.collect_top_row::<String>();
     println!("{answer}");
     Ok(())
@@ -1,3 +1,5 @@
+use std::io;
+
 use y2022::day05::*;
 
 fn main() {
@@ -8,8 +10,11 @@ fn main() {
 
 fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     let file = day05_file()?;
-    let (mut platform, lifts) = drawing(file);
-    platform.try_lifts(lifts)?;
+    let (mut platform, lifts) = drawing(file)?;
+    match animation_delay()? {
+        Some(delay) => animate(platform.steps(lifts), io::stdout(), delay)?,
+        None => platform.try_lifts(lifts)?,
+    }
     let answer = platform.collect_top_row::<String>();
     println!("{answer}");
     Ok(())
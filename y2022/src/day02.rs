@@ -1,10 +1,11 @@
 use crate::{LineReader, LinesParse, LinesParseMap, ParseControlFlow};
 
+use itertools::Itertools;
+
 use std::{
-    cmp::Ordering,
+    collections::HashMap,
     fs::File,
     io::{self, BufReader, Lines, Read},
-    ops::Add,
     str::FromStr,
 };
 
@@ -12,8 +13,8 @@ pub fn day02_file() -> io::Result<File> {
     super::input("day02")
 }
 
-type Score = u64;
-type Turns = usize;
+pub type Score = u64;
+pub type Turns = usize;
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Player {
@@ -24,8 +25,8 @@ pub struct Player {
 }
 
 impl Player {
-    fn update(&mut self, shape: Shape, outcome: Outcome) {
-        self.score = self.score + shape + outcome;
+    fn update(&mut self, rules: &Rules, shape: Shape, outcome: Outcome) {
+        self.score += rules.score(shape, outcome);
         match outcome {
             Outcome::Won => self.wins += 1,
             Outcome::Lost => self.losses += 1,
@@ -36,48 +37,177 @@ impl Player {
     pub fn score(&self) -> Score {
         self.score
     }
+
+    pub fn wins(&self) -> Turns {
+        self.wins
+    }
+
+    pub fn losses(&self) -> Turns {
+        self.losses
+    }
+
+    pub fn draws(&self) -> Turns {
+        self.draws
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        match self.wins + self.losses + self.draws {
+            0 => 0.0,
+            played => self.wins as f64 / played as f64,
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
-struct Players(Player, Player);
+#[derive(Debug, Clone, Default)]
+struct Players {
+    opponent: Player,
+    protagonist: Player,
+    history: Option<Vec<Round>>,
+}
 
 impl Players {
-    fn play(&mut self, shapes: Shapes) {
+    fn recording() -> Self {
+        let history = Some(Vec::new());
+        Self { history, ..Default::default() }
+    }
+
+    fn play(&mut self, rules: &Rules, turn: Turns, shapes: Shapes) {
         let (sha0, sha1) = shapes.into();
-        let (out0, out1) = Outcomes::resolve(shapes).into();
-        self.0.update(sha0, out0);
-        self.1.update(sha1, out1);
+        let (out0, out1) = Outcomes::resolve(rules, shapes).into();
+        self.opponent.update(rules, sha0, out0);
+        self.protagonist.update(rules, sha1, out1);
+        if let Some(history) = self.history.as_mut() {
+            let opponent = Play::new(sha0, out0, self.opponent.score());
+            let protagonist = Play::new(sha1, out1, self.protagonist.score());
+            history.push(Round { turn, opponent, protagonist });
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Play {
+    shape: Shape,
+    outcome: Outcome,
+    score: Score,
+}
+
+impl Play {
+    fn new(shape: Shape, outcome: Outcome, score: Score) -> Self {
+        Self { shape, outcome, score }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    // Running score, including this round.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Round {
+    turn: Turns,
+    opponent: Play,
+    protagonist: Play,
+}
+
+impl Round {
+    pub fn turn(&self) -> Turns {
+        self.turn
+    }
+
+    pub fn opponent(&self) -> &Play {
+        &self.opponent
+    }
+
+    pub fn protagonist(&self) -> &Play {
+        &self.protagonist
+    }
+
+    fn mirrored(self) -> Self {
+        let (opponent, protagonist) = (self.protagonist, self.opponent);
+        Self { turn: self.turn, opponent, protagonist }
     }
 }
 
 #[derive(Debug)]
 pub struct Game<R> {
     turn: Turns,
+    rules: Rules,
     players: Players,
     rounds: RowReader<R>,
 }
 
 impl<R: Read> Game<R> {
-    pub fn new(read: R) -> Self {
-        let (turn, players) = Default::default();
-        let rounds = RowReader::new(read);
-        Self { turn, players, rounds }
+    pub fn new(read: R, decoding: Decoding) -> Self {
+        let (turn, rules, players) = Default::default();
+        let rounds = RowReader::new(read, decoding);
+        Self { turn, rules, players, rounds }
+    }
+
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_history(mut self) -> Self {
+        self.players = Players::recording();
+        self
+    }
+
+    // Values after the second column are ignored instead of rejected,
+    // for guides with trailing comments.
+    pub fn with_lenient_rows(mut self) -> Self {
+        self.rounds.lenient = true;
+        self
     }
 
-    pub fn tournament1(mut self) -> Result<Statistics, RowError> {
-        let iter = self.rounds.into_iter().map(|row| Ok(Shapes::from(row?)));
-        for shapes in iter {
-            self.players.play(shapes?);
+    fn play(&mut self, shapes: Shapes) {
+        self.turn += 1;
+        self.players.play(&self.rules, self.turn, shapes);
+    }
+
+    fn instruction(&mut self) -> Option<Result<Instruction, RowError>> {
+        let instruction = self.rounds.next()?;
+        let check = |instruction: Instruction| {
+            let pos = self.rounds.reader.pos();
+            instruction.check(&self.rules).map_err(|err| RowError::new(pos, err.into()))
+        };
+        Some(instruction.and_then(check))
+    }
+
+    pub fn tournament(mut self) -> Result<Statistics, RowError> {
+        while let Some(instruction) = self.instruction() {
+            let shapes = instruction?.shapes(&self.rules);
+            self.play(shapes);
         }
         Ok(Statistics::new(self.turn, self.players))
     }
 
-    pub fn tournament2(mut self) -> Result<Statistics, RowError> {
-        let iter = self.rounds.into_iter().map(|row| Ok(Strategy::from(row?)));
-        for strat in iter {
-            self.players.play(strat?.solve());
+    // Plays every candidate meaning for the second column at once, only the
+    // raw symbols of the column are used, not the values they decode to.
+    pub fn search(mut self) -> Result<Search, SearchError> {
+        let symbols = self.rounds.decoding.response_symbols();
+        if symbols.len() != Mapping::SYMBOLS {
+            return Err(SearchError::Symbols(symbols.len()));
         }
-        Ok(Statistics::new(self.turn, self.players))
+        let mut candidates = Candidate::all();
+        while let Some(instruction) = self.instruction() {
+            let instruction = instruction?;
+            self.turn += 1;
+            let symbol = symbols.iter().position(|symbol| symbol == instruction.symbol());
+            let symbol = symbol.expect("decoded symbols are part of the decoding");
+            for candidate in candidates.iter_mut() {
+                candidate.play(&self.rules, self.turn, instruction.opponent(), symbol);
+            }
+        }
+        Ok(Search::new(self.turn, symbols, candidates))
     }
 }
 
@@ -93,92 +223,321 @@ impl Statistics {
     }
 
     pub fn opponent(&self) -> &Player {
-        &self.players.0
+        &self.players.opponent
     }
 
     pub fn protagonist(&self) -> &Player {
-        &self.players.1
+        &self.players.protagonist
     }
 
     pub fn turns(&self) -> &Turns {
         &self.turns
     }
+
+    // Only available if the game was configured with `Game::with_history`.
+    pub fn history(&self) -> Option<&[Round]> {
+        self.players.history.as_deref()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mapping {
+    Shapes([Shape; Mapping::SYMBOLS]),
+    Outcomes([Outcome; Mapping::SYMBOLS]),
+}
+
+impl Mapping {
+    const SYMBOLS: usize = 3;
+    const SHAPES: [Shape; Self::SYMBOLS] = [Shape::ROCK, Shape::PAPER, Shape::SCISSORS];
+    const OUTCOMES: [Outcome; Self::SYMBOLS] = [Outcome::Lost, Outcome::Draw, Outcome::Won];
+
+    fn all() -> impl Iterator<Item = Self> {
+        let shapes = Self::SHAPES.into_iter().permutations(Self::SYMBOLS);
+        let shapes = shapes.map(|p| Self::Shapes([p[0], p[1], p[2]]));
+        let outcomes = Self::OUTCOMES.into_iter().permutations(Self::SYMBOLS);
+        let outcomes = outcomes.map(|p| Self::Outcomes([p[0], p[1], p[2]]));
+        shapes.chain(outcomes)
+    }
+
+    fn shapes(&self, rules: &Rules, opponent: Shape, symbol: usize) -> Shapes {
+        match self {
+            Self::Shapes(shapes) => (opponent, shapes[symbol]).into(),
+            Self::Outcomes(outcomes) => Plan(opponent, outcomes[symbol]).solve(rules),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    mapping: Mapping,
+    players: Players,
+}
+
+impl Candidate {
+    fn all() -> Vec<Self> {
+        let players = Players::default();
+        Mapping::all().map(|mapping| Self { mapping, players: players.clone() }).collect()
+    }
+
+    fn play(&mut self, rules: &Rules, turn: Turns, opponent: Shape, symbol: usize) {
+        let shapes = self.mapping.shapes(rules, opponent, symbol);
+        self.players.play(rules, turn, shapes);
+    }
+
+    pub fn mapping(&self) -> Mapping {
+        self.mapping
+    }
+
+    pub fn opponent(&self) -> &Player {
+        &self.players.opponent
+    }
+
+    pub fn protagonist(&self) -> &Player {
+        &self.players.protagonist
+    }
+}
+
+#[derive(Debug)]
+pub struct Search {
+    turns: Turns,
+    symbols: Vec<String>,
+    candidates: Vec<Candidate>,
+}
+
+impl Search {
+    fn new(turns: Turns, symbols: Vec<String>, candidates: Vec<Candidate>) -> Self {
+        Self { turns, symbols, candidates }
+    }
+
+    // Second column symbols in sorted order, which is the order every
+    // `Mapping` assigns its values in.
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    pub fn table(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    // Ties are resolved in favor of the first candidate in the table.
+    pub fn best(&self) -> &Candidate {
+        let best = self.candidates.iter().rev().max_by_key(|c| c.protagonist().score());
+        best.expect("there is always a candidate for every mapping")
+    }
+
+    pub fn turns(&self) -> &Turns {
+        &self.turns
+    }
+}
+
+// SplitMix64, good enough to make simulations reproducible from a seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // PANIC: will panic if `bound` is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+pub trait Strategy {
+    // Rounds are seen from the bot's side, the bot is always the protagonist.
+    fn choose(&mut self, rules: &Rules, history: &[Round], rng: &mut Rng) -> Shape;
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn choose(&mut self, rules: &Rules, history: &[Round], rng: &mut Rng) -> Shape {
+        (**self).choose(rules, history, rng)
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
-struct Strategy(Shape, Outcome);
-
-impl Strategy {
-    fn solve(self) -> Shapes {
-        let choice = match (self.0, self.1) {
-            (Shape::Rock, Outcome::Draw)
-            | (Shape::Paper, Outcome::Lost)
-            | (Shape::Scissors, Outcome::Won) => Shape::Rock,
-            (Shape::Rock, Outcome::Won)
-            | (Shape::Paper, Outcome::Draw)
-            | (Shape::Scissors, Outcome::Lost) => Shape::Paper,
-            (Shape::Rock, Outcome::Lost)
-            | (Shape::Paper, Outcome::Won)
-            | (Shape::Scissors, Outcome::Draw) => Shape::Scissors,
-        };
-        (self.0, choice).into()
+pub struct Always(Shape);
+
+impl Always {
+    pub fn new(shape: Shape) -> Self {
+        Self(shape)
     }
 }
 
-impl From<Row> for Strategy {
-    fn from(row: Row) -> Self {
-        Self(Shape::from(row.0), Outcome::from(row.1))
+impl Strategy for Always {
+    fn choose(&mut self, _: &Rules, _: &[Round], _: &mut Rng) -> Shape {
+        self.0
     }
 }
 
 #[derive(Debug, Copy, Clone)]
-enum Outcome {
-    Lost,
-    Draw,
-    Won,
+pub struct Cycle(Shape);
+
+impl Cycle {
+    pub fn new(start: Shape) -> Self {
+        Self(start)
+    }
 }
 
-impl Outcome {
-    const LOST_SCORE: Score = 0;
-    const DRAW_SCORE: Score = 3;
-    const WON_SCORE: Score = 6;
+impl Strategy for Cycle {
+    fn choose(&mut self, rules: &Rules, history: &[Round], _: &mut Rng) -> Shape {
+        Shape((self.0.id() + history.len()) % rules.len())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Random;
+
+impl Strategy for Random {
+    fn choose(&mut self, rules: &Rules, _: &[Round], rng: &mut Rng) -> Shape {
+        Shape(rng.below(rules.len()))
+    }
 }
 
-impl From<Column1> for Outcome {
-    fn from(col1: Column1) -> Self {
-        match col1 {
-            Column1::X => Self::Lost,
-            Column1::Y => Self::Draw,
-            Column1::Z => Self::Won,
+// Beats the shape the other side played the most so far, shapes from a
+// history played under other rules are not counted.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Frequency;
+
+impl Strategy for Frequency {
+    fn choose(&mut self, rules: &Rules, history: &[Round], rng: &mut Rng) -> Shape {
+        let mut counts = vec![0; rules.len()];
+        for round in history {
+            if let Some(count) = counts.get_mut(round.opponent().shape().id()) {
+                *count += 1;
+            }
+        }
+        match predict(&counts) {
+            Some(prediction) => rules.shape_for(prediction, Outcome::Won),
+            None => Random.choose(rules, history, rng),
         }
     }
 }
 
-impl From<Outcome> for Score {
-    fn from(outcome: Outcome) -> Self {
-        match outcome {
-            Outcome::Lost => Outcome::LOST_SCORE,
-            Outcome::Draw => Outcome::DRAW_SCORE,
-            Outcome::Won => Outcome::WON_SCORE,
+// Beats the shape that most often followed the other side's last shape.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Markov;
+
+impl Strategy for Markov {
+    fn choose(&mut self, rules: &Rules, history: &[Round], rng: &mut Rng) -> Shape {
+        let shapes = history.iter().map(|round| round.opponent().shape().id());
+        let mut counts = vec![vec![0; rules.len()]; rules.len()];
+        for (prev, next) in shapes.tuple_windows() {
+            if let Some(count) = counts.get_mut(prev).and_then(|counts| counts.get_mut(next)) {
+                *count += 1;
+            }
+        }
+        let last = history.last().map(|round| round.opponent().shape().id());
+        match last.and_then(|last| counts.get(last)).and_then(|counts| predict(counts)) {
+            Some(prediction) => rules.shape_for(prediction, Outcome::Won),
+            None => Random.choose(rules, history, rng),
+        }
+    }
+}
+
+// Ties are resolved in favor of the lowest shape id.
+fn predict(counts: &[usize]) -> Option<Shape> {
+    let (id, &count) = counts.iter().enumerate().rev().max_by_key(|(_, &count)| count)?;
+    (count > 0).then_some(Shape(id))
+}
+
+// Follows the strategy guide, starting over when it runs out of rows.
+#[derive(Debug, Clone)]
+pub struct Scripted(Vec<Instruction>);
+
+impl Scripted {
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        Self(instructions)
+    }
+
+    pub fn read<R: Read>(rows: RowReader<R>) -> Result<Self, RowError> {
+        Ok(Self(rows.try_collect()?))
+    }
+}
+
+impl Strategy for Scripted {
+    fn choose(&mut self, rules: &Rules, history: &[Round], rng: &mut Rng) -> Shape {
+        match self.0.is_empty() {
+            true => Random.choose(rules, history, rng),
+            false => self.0[history.len() % self.0.len()].shapes(rules).1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Simulation<O, P> {
+    rules: Rules,
+    rng: Rng,
+    opponent: O,
+    protagonist: P,
+}
+
+impl<O: Strategy, P: Strategy> Simulation<O, P> {
+    pub fn new(opponent: O, protagonist: P, seed: u64) -> Self {
+        let (rules, rng) = (Rules::default(), Rng::new(seed));
+        Self { rules, rng, opponent, protagonist }
+    }
+
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Fails if a strategy chooses a shape that is not part of the rules.
+    pub fn run(mut self, rounds: Turns) -> Result<Statistics, RulesError> {
+        let mut players = Players::recording();
+        let mut mirrored = Vec::with_capacity(rounds);
+        for turn in 1..=rounds {
+            let history = players.history.as_deref().unwrap_or_default();
+            let sha0 = self.opponent.choose(&self.rules, &mirrored, &mut self.rng);
+            let sha1 = self.protagonist.choose(&self.rules, history, &mut self.rng);
+            let shapes = (self.rules.check(sha0)?, self.rules.check(sha1)?);
+            players.play(&self.rules, turn, shapes.into());
+            let last = players.history.as_deref().and_then(<[_]>::last);
+            mirrored.extend(last.copied().map(Round::mirrored));
         }
+        Ok(Statistics::new(rounds, players))
     }
 }
 
-impl Add<Outcome> for Score {
-    type Output = Score;
+#[derive(Debug, Copy, Clone)]
+struct Plan(Shape, Outcome);
 
-    fn add(self, rhs: Outcome) -> Self::Output {
-        self + Score::from(rhs)
+impl Plan {
+    fn solve(self, rules: &Rules) -> Shapes {
+        (self.0, rules.shape_for(self.0, self.1)).into()
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Lost,
+    Draw,
+    Won,
+}
+
+impl Outcome {
+    const LOST_SCORE: Score = 0;
+    const DRAW_SCORE: Score = 3;
+    const WON_SCORE: Score = 6;
+}
+
 #[derive(Debug, Copy, Clone)]
 struct Outcomes(Outcome, Outcome);
 
 impl Outcomes {
-    fn resolve(shapes: Shapes) -> Self {
-        let out0 = shapes.against();
-        let out1 = shapes.rev_against();
+    fn resolve(rules: &Rules, shapes: Shapes) -> Self {
+        let out0 = shapes.against(rules);
+        let out1 = shapes.rev_against(rules);
         Self(out0, out1)
     }
 }
@@ -189,88 +548,144 @@ impl From<Outcomes> for (Outcome, Outcome) {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
-}
+// The ordering is by shape id only, which shape wins a round is described
+// by `Rules::beats` and `Rules::loses_to`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Shape(usize);
 
 impl Shape {
+    pub const ROCK: Self = Self(0);
+    pub const PAPER: Self = Self(1);
+    pub const SCISSORS: Self = Self(2);
+
     const ROCK_SCORE: Score = 1;
     const PAPER_SCORE: Score = 2;
     const SCISSORS_SCORE: Score = 3;
 
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            (Self::Rock, Self::Rock)
-            | (Self::Paper, Self::Paper)
-            | (Self::Scissors, Self::Scissors) => Ordering::Equal,
-            (Self::Rock, Self::Scissors)
-            | (Self::Paper, Self::Rock)
-            | (Self::Scissors, Self::Paper) => Ordering::Greater,
-            (Self::Rock, Self::Paper)
-            | (Self::Paper, Self::Scissors)
-            | (Self::Scissors, Self::Rock) => Ordering::Less,
-        }
+    pub fn id(&self) -> usize {
+        self.0
     }
 
-    fn against(self, other: Self) -> Outcome {
-        match self.cmp(&other) {
-            Ordering::Equal => Outcome::Draw,
-            Ordering::Greater => Outcome::Won,
-            Ordering::Less => Outcome::Lost,
+    pub fn against(self, other: Self, rules: &Rules) -> Outcome {
+        if rules.beats(self, other) {
+            Outcome::Won
+        } else if rules.loses_to(self, other) {
+            Outcome::Lost
+        } else {
+            Outcome::Draw
         }
     }
 }
 
-impl Add<Shape> for Score {
-    type Output = Score;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    shapes: Vec<Score>,
+    lost: Score,
+    draw: Score,
+    won: Score,
+}
 
-    fn add(self, rhs: Shape) -> Self::Output {
-        self + Score::from(rhs)
+impl Rules {
+    const MIN_SHAPES: usize = 3;
+
+    // Shapes are identified by their position in `scores`, every shape
+    // beats the shapes that are an odd distance behind it in the cycle.
+    pub fn new<I: IntoIterator<Item = Score>>(scores: I) -> Result<Self, RulesError> {
+        let shapes = scores.into_iter().collect::<Vec<_>>();
+        match shapes.len() {
+            len if len < Self::MIN_SHAPES => Err(RulesError::TooFew(len)),
+            len if len % 2 == 0 => Err(RulesError::Even(len)),
+            _ => {
+                let (lost, draw, won) = Self::outcome_scores();
+                Ok(Self { shapes, lost, draw, won })
+            }
+        }
     }
-}
 
-impl PartialOrd for Shape {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    // Rock, Paper, Scissors, Spock and Lizard, scored from 1 to 5.
+    pub fn rpsls() -> Self {
+        Self::new(1..=5).expect("five shapes is a valid rule set")
     }
-}
 
-impl Ord for Shape {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.cmp(other)
+    pub fn with_outcome_scores(mut self, lost: Score, draw: Score, won: Score) -> Self {
+        (self.lost, self.draw, self.won) = (lost, draw, won);
+        self
     }
-}
 
-impl From<Shape> for Score {
-    fn from(shape: Shape) -> Self {
-        match shape {
-            Shape::Rock => Shape::ROCK_SCORE,
-            Shape::Paper => Shape::PAPER_SCORE,
-            Shape::Scissors => Shape::SCISSORS_SCORE,
-        }
+    fn outcome_scores() -> (Score, Score, Score) {
+        (Outcome::LOST_SCORE, Outcome::DRAW_SCORE, Outcome::WON_SCORE)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    pub fn shape(&self, id: usize) -> Option<Shape> {
+        (id < self.len()).then_some(Shape(id))
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> {
+        (0..self.len()).map(Shape)
+    }
+
+    // Shapes are checked wherever they enter a game, so that their scores
+    // come from the rules instead of a cycle they don't belong to.
+    pub fn check(&self, shape: Shape) -> Result<Shape, RulesError> {
+        self.shape(shape.id()).ok_or_else(|| RulesError::Shape(shape.id()))
+    }
+
+    // Ids are reduced to the cycle, so shapes from larger rule sets don't panic.
+    fn distance(&self, shape: Shape, other: Shape) -> usize {
+        let len = self.len();
+        (len + shape.id() % len - other.id() % len) % len
+    }
+
+    pub fn beats(&self, shape: Shape, other: Shape) -> bool {
+        self.distance(shape, other) % 2 == 1
+    }
+
+    pub fn loses_to(&self, shape: Shape, other: Shape) -> bool {
+        self.beats(other, shape)
     }
-}
 
-impl From<Column0> for Shape {
-    fn from(col: Column0) -> Self {
-        match col {
-            Column0::A => Self::Rock,
-            Column0::B => Self::Paper,
-            Column0::C => Self::Scissors,
+    // With more than three shapes, several of them lead to the same outcome,
+    // the closest one to `other` in the cycle is chosen.
+    pub fn shape_for(&self, other: Shape, outcome: Outcome) -> Shape {
+        let (len, id) = (self.len(), other.id());
+        let choice = match outcome {
+            Outcome::Draw => id,
+            Outcome::Won => id + 1,
+            Outcome::Lost => id + len - 1,
+        };
+        Shape(choice % len)
+    }
+
+    // PANIC: will panic if `shape` is not part of the rules.
+    pub fn shape_score(&self, shape: Shape) -> Score {
+        self.shapes[shape.id()]
+    }
+
+    pub fn outcome_score(&self, outcome: Outcome) -> Score {
+        match outcome {
+            Outcome::Lost => self.lost,
+            Outcome::Draw => self.draw,
+            Outcome::Won => self.won,
         }
     }
+
+    pub fn score(&self, shape: Shape, outcome: Outcome) -> Score {
+        self.shape_score(shape) + self.outcome_score(outcome)
+    }
 }
 
-impl From<Column1> for Shape {
-    fn from(col: Column1) -> Self {
-        match col {
-            Column1::Y => Self::Paper,
-            Column1::X => Self::Rock,
-            Column1::Z => Self::Scissors,
-        }
+impl Default for Rules {
+    fn default() -> Self {
+        let scores = [Shape::ROCK_SCORE, Shape::PAPER_SCORE, Shape::SCISSORS_SCORE];
+        Self::new(scores).expect("rock, paper and scissors is a valid rule set")
     }
 }
 
@@ -278,12 +693,12 @@ impl From<Column1> for Shape {
 struct Shapes(Shape, Shape);
 
 impl Shapes {
-    fn against(self) -> Outcome {
-        self.0.against(self.1)
+    fn against(self, rules: &Rules) -> Outcome {
+        self.0.against(self.1, rules)
     }
 
-    fn rev_against(self) -> Outcome {
-        self.1.against(self.0)
+    fn rev_against(self, rules: &Rules) -> Outcome {
+        self.1.against(self.0, rules)
     }
 }
 
@@ -299,121 +714,175 @@ impl From<(Shape, Shape)> for Shapes {
     }
 }
 
-trait Column: TryFrom<char, Error = ColumnParseError> {
-    const POS: usize;
+#[derive(Debug, Clone)]
+struct Symbols<T> {
+    pos: usize,
+    map: HashMap<String, T>,
+}
 
-    fn col_from_str(s: &str) -> Result<Self, ColumnError> {
-        let mut chars = s.chars();
-        let col = chars.next().ok_or(ColumnError::Empty(Self::POS))?;
-        let None = chars.next() else {
-            return Err(ColumnError::Larger(Self::POS));
-        };
-        Ok(col.try_into()?)
+impl<T: Copy> Symbols<T> {
+    fn new<I, S>(pos: usize, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = (S, T)>,
+        S: Into<String>,
+    {
+        let map = symbols.into_iter().map(|(sym, val)| (sym.into(), val)).collect();
+        Self { pos, map }
     }
 
-    fn col_try_from(value: Option<&str>) -> Result<Self, ColumnError> {
-        Self::col_from_str(value.ok_or(ColumnError::Empty(Self::POS))?)
+    fn decode(&self, value: &str) -> Result<T, ColumnParseError> {
+        self.decode_at(self.pos, value)
     }
-}
 
-#[derive(Debug, Copy, Clone)]
-enum Column0 {
-    A,
-    B,
-    C,
+    fn decode_at(&self, pos: usize, value: &str) -> Result<T, ColumnParseError> {
+        let decoded = self.map.get(value).copied();
+        decoded.ok_or_else(|| ColumnParseError::new(pos, value))
+    }
+
+    fn symbols(&self) -> Vec<String> {
+        self.map.keys().cloned().sorted().collect()
+    }
 }
 
-impl Column0 {
-    const POS: usize = 0;
+#[derive(Debug, Clone)]
+enum Responses {
+    Shapes(Symbols<Shape>),
+    Outcomes(Symbols<Outcome>),
 }
 
-impl TryFrom<char> for Column0 {
-    type Error = ColumnParseError;
+#[derive(Debug, Clone)]
+pub struct Decoding {
+    opponent: Symbols<Shape>,
+    responses: Responses,
+}
 
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            'A' => Ok(Self::A),
-            'B' => Ok(Self::B),
-            'C' => Ok(Self::C),
-            _ => Err(Self::Error::new(Self::POS, value)),
-        }
+impl Decoding {
+    const OPPONENT: [(&'static str, Shape); 3] =
+        [("A", Shape::ROCK), ("B", Shape::PAPER), ("C", Shape::SCISSORS)];
+    const SHAPES: [(&'static str, Shape); 3] =
+        [("X", Shape::ROCK), ("Y", Shape::PAPER), ("Z", Shape::SCISSORS)];
+    const OUTCOMES: [(&'static str, Outcome); 3] =
+        [("X", Outcome::Lost), ("Y", Outcome::Draw), ("Z", Outcome::Won)];
+
+    // The second column tells which shape the protagonist must play.
+    pub fn with_shapes<O, P, S, T>(opponent: O, protagonist: P) -> Self
+    where
+        O: IntoIterator<Item = (S, Shape)>,
+        P: IntoIterator<Item = (T, Shape)>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let opponent = Symbols::new(Row::OPPONENT_POS, opponent);
+        let responses = Responses::Shapes(Symbols::new(Row::RESPONSE_POS, protagonist));
+        Self { opponent, responses }
     }
-}
 
-impl Column for Column0 {
-    const POS: usize = Self::POS;
-}
+    // The second column tells how the round must end for the protagonist.
+    pub fn with_outcomes<O, P, S, T>(opponent: O, protagonist: P) -> Self
+    where
+        O: IntoIterator<Item = (S, Shape)>,
+        P: IntoIterator<Item = (T, Outcome)>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let opponent = Symbols::new(Row::OPPONENT_POS, opponent);
+        let responses = Responses::Outcomes(Symbols::new(Row::RESPONSE_POS, protagonist));
+        Self { opponent, responses }
+    }
 
-impl FromStr for Column0 {
-    type Err = ColumnError;
+    pub fn shapes() -> Self {
+        Self::with_shapes(Self::OPPONENT, Self::SHAPES)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Column::col_from_str(s)
+    pub fn outcomes() -> Self {
+        Self::with_outcomes(Self::OPPONENT, Self::OUTCOMES)
     }
-}
 
-impl TryFrom<Option<&str>> for Column0 {
-    type Error = ColumnError;
+    fn response_symbols(&self) -> Vec<String> {
+        match &self.responses {
+            Responses::Shapes(shapes) => shapes.symbols(),
+            Responses::Outcomes(outcomes) => outcomes.symbols(),
+        }
+    }
 
-    fn try_from(value: Option<&str>) -> Result<Self, Self::Error> {
-        Column::col_try_from(value)
+    fn decode(&self, row: &Row) -> Result<Instruction, ColumnParseError> {
+        let opponent = self.opponent.decode(&row.0)?;
+        let response = match &self.responses {
+            Responses::Shapes(shapes) => Response::Shape(shapes.decode(&row.1)?),
+            Responses::Outcomes(outcomes) => Response::Outcome(outcomes.decode(&row.1)?),
+        };
+        let symbol = row.1.clone();
+        Ok(Instruction { opponent, response, symbol })
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Column1 {
-    Y,
-    X,
-    Z,
+impl Default for Decoding {
+    fn default() -> Self {
+        Self::shapes()
+    }
 }
 
-impl Column1 {
-    const POS: usize = 1;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Response {
+    Shape(Shape),
+    Outcome(Outcome),
 }
 
-impl TryFrom<char> for Column1 {
-    type Error = ColumnParseError;
-
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            'Y' => Ok(Self::Y),
-            'X' => Ok(Self::X),
-            'Z' => Ok(Self::Z),
-            _ => Err(Self::Error::new(Self::POS, value)),
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    opponent: Shape,
+    response: Response,
+    symbol: String,
 }
 
-impl Column for Column1 {
-    const POS: usize = Self::POS;
-}
+impl Instruction {
+    pub fn opponent(&self) -> Shape {
+        self.opponent
+    }
 
-impl FromStr for Column1 {
-    type Err = ColumnError;
+    pub fn response(&self) -> Response {
+        self.response
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Column::col_from_str(s)
+    // The second column as written in the guide, before decoding.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
     }
-}
 
-impl TryFrom<Option<&str>> for Column1 {
-    type Error = ColumnError;
+    fn check(self, rules: &Rules) -> Result<Self, RulesError> {
+        rules.check(self.opponent)?;
+        if let Response::Shape(shape) = self.response {
+            rules.check(shape)?;
+        }
+        Ok(self)
+    }
 
-    fn try_from(value: Option<&str>) -> Result<Self, Self::Error> {
-        Column::col_try_from(value)
+    fn shapes(&self, rules: &Rules) -> Shapes {
+        match self.response {
+            Response::Shape(shape) => (self.opponent, shape).into(),
+            Response::Outcome(outcome) => Plan(self.opponent, outcome).solve(rules),
+        }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Row(Column0, Column1);
+#[derive(Debug, Clone)]
+pub struct Row(String, String, Option<String>);
 
 impl Row {
     const DELIMETER: &'static str = "<whitespace>";
-}
+    const OPPONENT_POS: usize = 0;
+    const RESPONSE_POS: usize = 1;
+    const TRAILING_POS: usize = 2;
 
-impl From<Row> for Shapes {
-    fn from(row: Row) -> Self {
-        Self(Shape::from(row.0), Shape::from(row.1))
+    fn column(value: Option<&str>, pos: usize) -> Result<String, ColumnError> {
+        Ok(value.ok_or(ColumnError::Empty(pos))?.to_owned())
+    }
+
+    fn strict(self) -> Result<Self, RowParseError> {
+        match self.2 {
+            Some(token) => Err(RowParseError::trailing(Self::TRAILING_POS, token)),
+            None => Ok(self),
+        }
     }
 }
 
@@ -423,24 +892,42 @@ impl FromStr for Row {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut cols = s.split_whitespace().into_iter().peekable();
         cols.peek().ok_or(RowParseError::Delimeter(Self::DELIMETER))?;
-        let col0 = Column0::try_from(cols.next())?;
-        let col1 = Column1::try_from(cols.next())?;
-        Ok(Self(col0, col1))
+        let col0 = Self::column(cols.next(), Self::OPPONENT_POS)?;
+        let col1 = Self::column(cols.next(), Self::RESPONSE_POS)?;
+        let trailing = cols.next().map(str::to_owned);
+        Ok(Self(col0, col1, trailing))
     }
 }
 
 #[derive(Debug)]
-pub struct RowReader<R>(LineReader<R>);
+pub struct RowReader<R> {
+    reader: LineReader<R>,
+    decoding: Decoding,
+    lenient: bool,
+}
 
 impl<R: Read> RowReader<R> {
-    pub fn new(read: R) -> Self {
+    pub fn new(read: R, decoding: Decoding) -> Self {
         let reader = LineReader::new(read);
-        Self(reader)
+        Self { reader, decoding, lenient: false }
+    }
+
+    pub fn lenient(mut self) -> Self {
+        self.lenient = true;
+        self
+    }
+
+    fn decode(&self, mut row: Row) -> Result<Instruction, RowParseError> {
+        if !self.lenient {
+            row = row.strict()?;
+        }
+        let instruction = self.decoding.decode(&row).map_err(ColumnError::from)?;
+        Ok(instruction)
     }
 }
 
 impl<R: Read> Iterator for RowReader<R> {
-    type Item = Result<Row, RowError>;
+    type Item = Result<Instruction, RowError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.parse_next_map()
@@ -457,41 +944,279 @@ impl<R: Read> LinesParse for RowReader<R> {
     type Lines<'s> = &'s mut Lines<BufReader<R>> where Self: 's;
 
     fn lines(&mut self) -> Self::Lines<'_> {
-        self.0.lines()
+        self.reader.lines()
     }
 
     fn every_line(&mut self) {
-        self.0.advance_pos()
+        self.reader.advance_pos()
     }
 }
 
 impl<R: Read> LinesParseMap for RowReader<R> {
-    type Result = Result<Self::Item, RowError>;
+    type Result = Result<Instruction, RowError>;
+
+    fn map(&self, res: Result<Self::Item, Self::Error>) -> Self::Result {
+        let res = res.and_then(|row| Ok(self.decode(row)?));
+        res.map_err(|err| RowError::new(self.reader.pos(), err))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry(String, Vec<String>);
+
+impl Entry {
+    const NAME_POS: usize = 0;
+    const MOVES_POS: usize = 1;
+}
+
+impl FromStr for Entry {
+    type Err = RowParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cols = s.split_whitespace();
+        let name = cols.next().ok_or(ColumnError::Empty(Self::NAME_POS))?.to_owned();
+        let moves = cols.map(str::to_owned).collect::<Vec<_>>();
+        if moves.is_empty() {
+            return Err(ColumnError::Empty(Self::MOVES_POS).into());
+        }
+        Ok(Self(name, moves))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entrant {
+    name: String,
+    shapes: Vec<Shape>,
+}
+
+impl Entrant {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+}
+
+#[derive(Debug)]
+pub struct EntrantReader<R> {
+    reader: LineReader<R>,
+    symbols: Symbols<Shape>,
+}
+
+impl<R: Read> EntrantReader<R> {
+    pub fn new(read: R) -> Self {
+        Self::with_symbols(read, Decoding::OPPONENT)
+    }
+
+    pub fn with_symbols<I, S>(read: R, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Shape)>,
+        S: Into<String>,
+    {
+        let reader = LineReader::new(read);
+        let symbols = Symbols::new(Entry::MOVES_POS, symbols);
+        Self { reader, symbols }
+    }
+
+    fn decode(&self, entry: Entry) -> Result<Entrant, ColumnParseError> {
+        let Entry(name, moves) = entry;
+        let moves = moves.iter().enumerate();
+        let shapes = moves.map(|(pos, m)| self.symbols.decode_at(Entry::MOVES_POS + pos, m));
+        let shapes = shapes.try_collect()?;
+        Ok(Entrant { name, shapes })
+    }
+}
+
+impl<R: Read> Iterator for EntrantReader<R> {
+    type Item = Result<Entrant, RowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parse_next_map()
+    }
+}
+
+impl<R> ParseControlFlow for EntrantReader<R> {
+    type Item = Entry;
+    type ParseError = RowParseError;
+}
+
+impl<R: Read> LinesParse for EntrantReader<R> {
+    type Error = RowErrorSource;
+    type Lines<'s> = &'s mut Lines<BufReader<R>> where Self: 's;
+
+    fn lines(&mut self) -> Self::Lines<'_> {
+        self.reader.lines()
+    }
+
+    fn every_line(&mut self) {
+        self.reader.advance_pos()
+    }
+}
+
+impl<R: Read> LinesParseMap for EntrantReader<R> {
+    type Result = Result<Entrant, RowError>;
 
     fn map(&self, res: Result<Self::Item, Self::Error>) -> Self::Result {
-        res.map_err(|err| RowError::new(self.0.pos(), err))
+        let decode =
+            |entry| self.decode(entry).map_err(|e| RowParseError::from(ColumnError::from(e)));
+        let res = res.and_then(|entry| Ok(decode(entry)?));
+        res.map_err(|err| RowError::new(self.reader.pos(), err))
+    }
+}
+
+#[derive(Debug)]
+pub struct RoundRobin<R> {
+    rules: Rules,
+    entrants: EntrantReader<R>,
+}
+
+impl<R: Read> RoundRobin<R> {
+    pub fn new(entrants: EntrantReader<R>) -> Self {
+        let rules = Rules::default();
+        Self { rules, entrants }
+    }
+
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Every pair of entrants plays their moves side by side, until one of
+    // them runs out of moves.
+    pub fn play(self) -> Result<Standings, TournamentError> {
+        let entrants = self.entrants.collect::<Result<Vec<_>, _>>()?;
+        if let Some(dup) = entrants.iter().map(Entrant::name).duplicates().next() {
+            return Err(TournamentError::Duplicate(dup.to_owned()));
+        }
+        for entrant in entrants.iter() {
+            let rules = |source| TournamentError::Rules { name: entrant.name.clone(), source };
+            for &shape in entrant.shapes() {
+                self.rules.check(shape).map_err(rules)?;
+            }
+        }
+        let mut standings = Standings::new(&entrants);
+        for ((id0, ent0), (id1, ent1)) in entrants.iter().enumerate().tuple_combinations() {
+            for (&sha0, &sha1) in ent0.shapes().iter().zip(ent1.shapes()) {
+                standings.play(&self.rules, (id0, id1), (sha0, sha1).into());
+            }
+        }
+        Ok(standings)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Standing {
+    name: String,
+    player: Player,
+}
+
+impl Standing {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Standings {
+    standings: Vec<Standing>,
+    head_to_head: Vec<Vec<Player>>,
+}
+
+impl Standings {
+    fn new(entrants: &[Entrant]) -> Self {
+        let standings =
+            entrants.iter().map(|e| Standing { name: e.name.clone(), player: Player::default() });
+        let head_to_head = vec![vec![Player::default(); entrants.len()]; entrants.len()];
+        Self { standings: standings.collect(), head_to_head }
+    }
+
+    fn play(&mut self, rules: &Rules, (id0, id1): (usize, usize), shapes: Shapes) {
+        let (sha0, sha1) = shapes.into();
+        let (out0, out1) = Outcomes::resolve(rules, shapes).into();
+        self.standings[id0].player.update(rules, sha0, out0);
+        self.standings[id1].player.update(rules, sha1, out1);
+        self.head_to_head[id0][id1].update(rules, sha0, out0);
+        self.head_to_head[id1][id0].update(rules, sha1, out1);
+    }
+
+    fn id(&self, name: &str) -> Option<usize> {
+        self.standings.iter().position(|standing| standing.name == name)
+    }
+
+    // Entrants in input order.
+    pub fn entrants(&self) -> &[Standing] {
+        &self.standings
+    }
+
+    // Ordered by score, then by wins, then by input order.
+    pub fn table(&self) -> Vec<&Standing> {
+        let key = |s: &&Standing| (s.player.score(), s.player.wins());
+        self.standings.iter().sorted_by(|a, b| key(b).cmp(&key(a))).collect()
+    }
+
+    // How `name` did in the rounds played against `other`.
+    pub fn head_to_head(&self, name: &str, other: &str) -> Option<&Player> {
+        let (id, other) = (self.id(name)?, self.id(other)?);
+        Some(&self.head_to_head[id][other])
     }
 }
 
 #[derive(Debug, Copy, Clone, thiserror::Error)]
+pub enum RulesError {
+    #[error("rules need at least 3 shapes, found: {0}")]
+    TooFew(usize),
+    #[error("rules need an odd number of shapes, found: {0}")]
+    Even(usize),
+    #[error("shape id: {0} is not part of the rules")]
+    Shape(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    #[error(transparent)]
+    Row(#[from] RowError),
+    #[error("search needs exactly 3 second column symbols, found: {0}")]
+    Symbols(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TournamentError {
+    #[error(transparent)]
+    Row(#[from] RowError),
+    #[error("duplicate entrant: `{0}`")]
+    Duplicate(String),
+    #[error("entrant: `{name}`, {source}")]
+    Rules {
+        name: String,
+        #[source]
+        source: RulesError,
+    },
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum ColumnError {
     #[error(transparent)]
     Parse(#[from] ColumnParseError),
     #[error("missing value for column: {0}")]
     Empty(usize),
-    #[error("value for column: {0} is too large")]
-    Larger(usize),
 }
 
-#[derive(Debug, Copy, Clone, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 #[error("invalid value: `{value}` at column: {pos}")]
 pub struct ColumnParseError {
     pos: usize,
-    value: char,
+    value: String,
 }
 
 impl ColumnParseError {
-    fn new(pos: usize, value: char) -> Self {
+    fn new(pos: usize, value: &str) -> Self {
+        let value = value.to_owned();
         Self { pos, value }
     }
 }
@@ -516,12 +1241,303 @@ pub enum RowErrorSource {
     IO(#[from] io::Error),
     #[error(transparent)]
     Parse(#[from] RowParseError),
+    #[error(transparent)]
+    Rules(#[from] RulesError),
 }
 
-#[derive(Debug, Copy, Clone, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum RowParseError {
     #[error(transparent)]
     Column(#[from] ColumnError),
     #[error("delimeter: `{0}` was not found")]
     Delimeter(&'static str),
+    #[error("unexpected trailing value: `{token}` at column: {pos}")]
+    Trailing { pos: usize, token: String },
+}
+
+impl RowParseError {
+    fn trailing(pos: usize, token: String) -> Self {
+        Self::Trailing { pos, token }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    const SAMPLE: &str = "A Y
+B X
+C Z";
+
+    fn test_game(decoding: Decoding) -> Game<Cursor<&'static str>> {
+        Game::new(Cursor::new(SAMPLE), decoding).with_history()
+    }
+
+    #[test]
+    fn solve_sample_tournament1() {
+        let stats = test_game(Decoding::shapes()).tournament().unwrap();
+        let player = stats.protagonist();
+        assert_eq!(15, player.score());
+        assert_eq!((1, 1, 1), (player.wins(), player.losses(), player.draws()));
+        assert_eq!(3, *stats.turns());
+    }
+
+    #[test]
+    fn solve_sample_tournament2() {
+        let stats = test_game(Decoding::outcomes()).tournament().unwrap();
+        assert_eq!(12, stats.protagonist().score());
+        assert_eq!(3, *stats.turns());
+    }
+
+    #[test]
+    fn record_sample_history() {
+        let stats = test_game(Decoding::shapes()).tournament().unwrap();
+        let history = stats.history().unwrap();
+        let turns = history.iter().map(Round::turn).collect::<Vec<_>>();
+        assert_eq!(vec![1, 2, 3], turns);
+        let first = history[0].protagonist();
+        assert_eq!(
+            (Shape::PAPER, Outcome::Won, 8),
+            (first.shape(), first.outcome(), first.score())
+        );
+        let last = history[2].opponent();
+        assert_eq!(stats.opponent().score(), last.score());
+        let game = Game::new(Cursor::new(SAMPLE), Decoding::shapes());
+        assert!(game.tournament().unwrap().history().is_none());
+    }
+
+    #[test]
+    fn rpsls_rules() {
+        let rules = Rules::rpsls();
+        let [rock, paper, scissors, spock, lizard] =
+            [0, 1, 2, 3, 4].map(|id| rules.shape(id).unwrap());
+        let wins = [
+            (scissors, paper),
+            (paper, rock),
+            (rock, lizard),
+            (lizard, spock),
+            (spock, scissors),
+            (scissors, lizard),
+            (lizard, paper),
+            (paper, spock),
+            (spock, rock),
+            (rock, scissors),
+        ];
+        for (winner, loser) in wins {
+            assert_eq!(Outcome::Won, winner.against(loser, &rules));
+            assert_eq!(Outcome::Lost, loser.against(winner, &rules));
+        }
+        assert_eq!(Outcome::Draw, spock.against(spock, &rules));
+        // Draw with rock (1 + 3), lose with rock (1 + 0), win with spock (4 + 6).
+        let game = Game::new(Cursor::new(SAMPLE), Decoding::outcomes());
+        let stats = game.with_rules(rules).tournament().unwrap();
+        assert_eq!(15, stats.protagonist().score());
+    }
+
+    #[test]
+    fn search_sample_decodings() {
+        let search = Game::new(Cursor::new(SAMPLE), Decoding::shapes()).search().unwrap();
+        assert_eq!(12, search.table().len());
+        assert_eq!(3, *search.turns());
+        let part1 = Mapping::Shapes([Shape::ROCK, Shape::PAPER, Shape::SCISSORS]);
+        let part2 = Mapping::Outcomes([Outcome::Lost, Outcome::Draw, Outcome::Won]);
+        let score = |mapping| {
+            let candidate = search.table().iter().find(|c| c.mapping() == mapping).unwrap();
+            candidate.protagonist().score()
+        };
+        assert_eq!((15, 12), (score(part1), score(part2)));
+        let best = search.best();
+        assert!(search
+            .table()
+            .iter()
+            .all(|c| c.protagonist().score() <= best.protagonist().score()));
+        // X as scissors, Y as paper and Z as rock wins every round.
+        let wins = Mapping::Shapes([Shape::SCISSORS, Shape::PAPER, Shape::ROCK]);
+        assert_eq!((wins, 24), (best.mapping(), best.protagonist().score()));
+    }
+
+    #[test]
+    fn search_custom_decodings() {
+        let scores = |decoding| {
+            let search = Game::new(Cursor::new(SAMPLE), decoding).search().unwrap();
+            let scores = search.table().iter().map(|c| c.protagonist().score());
+            (search.symbols().to_vec(), scores.collect::<Vec<_>>())
+        };
+        let shuffled = [("Z", Shape::ROCK), ("X", Shape::PAPER), ("Y", Shape::SCISSORS)];
+        let shuffled = Decoding::with_shapes(Decoding::OPPONENT, shuffled);
+        assert_eq!(scores(Decoding::shapes()), scores(shuffled));
+        assert_eq!(scores(Decoding::shapes()), scores(Decoding::outcomes()));
+
+        let codes = [("0", Outcome::Lost), ("1", Outcome::Draw), ("2", Outcome::Won)];
+        let decoding = Decoding::with_outcomes(Decoding::OPPONENT, codes);
+        let search = Game::new(Cursor::new("A 1\nB 0\nC 2"), decoding).search().unwrap();
+        assert_eq!(["0", "1", "2"], search.symbols());
+        assert_eq!(24, search.best().protagonist().score());
+
+        let four = Decoding::SHAPES.into_iter().chain([("W", Shape::ROCK)]);
+        let decoding = Decoding::with_shapes(Decoding::OPPONENT, four);
+        let err = Game::new(Cursor::new(SAMPLE), decoding).search().unwrap_err();
+        assert!(matches!(err, SearchError::Symbols(4)));
+    }
+
+    #[test]
+    fn custom_decodings() {
+        let words = [("rock", Shape::ROCK), ("paper", Shape::PAPER), ("scissors", Shape::SCISSORS)];
+        let codes = [("0", Outcome::Lost), ("1", Outcome::Draw), ("2", Outcome::Won)];
+        let input = Cursor::new("rock 1\npaper 0\nscissors 2");
+        let stats = Game::new(input, Decoding::with_outcomes(words, codes)).tournament().unwrap();
+        assert_eq!(12, stats.protagonist().score());
+
+        let lowercase = [("a", Shape::ROCK), ("b", Shape::PAPER), ("c", Shape::SCISSORS)];
+        let input = Cursor::new("a paper\nb rock\nc scissors");
+        let stats = Game::new(input, Decoding::with_shapes(lowercase, words)).tournament().unwrap();
+        assert_eq!(15, stats.protagonist().score());
+    }
+
+    #[test]
+    fn invalid_column() {
+        let input = Cursor::new("A Y\nB W");
+        let mut rows = RowReader::new(input, Decoding::shapes());
+        assert!(rows.next().unwrap().is_ok());
+        let err = rows.next().unwrap().unwrap_err();
+        assert_eq!(2, err.pos);
+        let RowErrorSource::Parse(RowParseError::Column(ColumnError::Parse(err))) = err.source else {
+            panic!("expected a column parse error");
+        };
+        assert_eq!((1, "W"), (err.pos, err.value.as_str()));
+    }
+
+    #[test]
+    fn trailing_columns() {
+        const COMMENTED: &str = "A Y # paper\nB X\nC Z # scissors";
+        let mut rows = RowReader::new(Cursor::new(COMMENTED), Decoding::shapes());
+        let err = rows.next().unwrap().unwrap_err();
+        assert_eq!(1, err.pos);
+        let RowErrorSource::Parse(RowParseError::Trailing { pos, token }) = err.source else {
+            panic!("expected a trailing column error");
+        };
+        assert_eq!((2, "#"), (pos, token.as_str()));
+
+        let game = Game::new(Cursor::new(COMMENTED), Decoding::shapes()).with_lenient_rows();
+        assert_eq!(15, game.tournament().unwrap().protagonist().score());
+        let rows = RowReader::new(Cursor::new(COMMENTED), Decoding::shapes()).lenient();
+        assert!(rows.into_iter().all(|row| row.is_ok()));
+    }
+
+    #[test]
+    fn round_robin_standings() {
+        const ENTRANTS: &str = "rocky A A A
+papyrus B B B
+
+edward C C C
+chaos A B C";
+        let entrants = EntrantReader::new(Cursor::new(ENTRANTS));
+        let standings = RoundRobin::new(entrants).play().unwrap();
+        let table = standings.table();
+        let names = table.iter().map(|s| s.name()).collect::<Vec<_>>();
+        assert_eq!(vec!["edward", "papyrus", "chaos", "rocky"], names);
+        let edward = table[0].player();
+        assert_eq!((4, 4, 1), (edward.wins(), edward.losses(), edward.draws()));
+        assert_eq!(54, edward.score());
+        let rocky = standings.head_to_head("rocky", "edward").unwrap();
+        assert_eq!((3, 0, 0), (rocky.wins(), rocky.losses(), rocky.draws()));
+        assert_eq!(21, rocky.score());
+        assert_eq!(0, standings.head_to_head("edward", "rocky").unwrap().wins());
+        assert!(standings.head_to_head("rocky", "nobody").is_none());
+
+        let duplicates = EntrantReader::new(Cursor::new("a A\nb B\na C"));
+        let err = RoundRobin::new(duplicates).play().unwrap_err();
+        assert!(matches!(err, TournamentError::Duplicate(name) if name == "a"));
+
+        let err = EntrantReader::new(Cursor::new("a A D")).next().unwrap().unwrap_err();
+        let RowErrorSource::Parse(RowParseError::Column(ColumnError::Parse(err))) = err.source else {
+            panic!("expected a column parse error");
+        };
+        assert_eq!((2, "D"), (err.pos, err.value.as_str()));
+    }
+
+    #[test]
+    fn simulate_bots() {
+        let sim = Simulation::new(Always::new(Shape::ROCK), Frequency, 7);
+        let stats = sim.run(100).unwrap();
+        assert_eq!(100, *stats.turns());
+        assert_eq!(100, stats.history().unwrap().len());
+        assert!(stats.protagonist().wins() >= 99);
+        assert!(stats.protagonist().win_rate() > stats.opponent().win_rate());
+
+        let stats = Simulation::new(Cycle::new(Shape::PAPER), Markov, 7).run(100).unwrap();
+        assert!(stats.protagonist().wins() >= 95);
+
+        let rows = RowReader::new(Cursor::new(SAMPLE), Decoding::shapes());
+        let script = Scripted::read(rows).unwrap();
+        let stats = Simulation::new(Cycle::new(Shape::ROCK), script, 7).run(6).unwrap();
+        assert_eq!(2 * 15, stats.protagonist().score());
+
+        let run = |seed| Simulation::new(Random, Box::new(Markov), seed).run(50).unwrap();
+        let (run0, run1) = (run(42), run(42));
+        assert_eq!(run0.protagonist().score(), run1.protagonist().score());
+        assert_eq!(run0.opponent().score(), run1.opponent().score());
+
+        let spock = Rules::rpsls().shape(3).unwrap();
+        let err = Simulation::new(Always::new(spock), Markov, 7).run(1).unwrap_err();
+        assert!(matches!(err, RulesError::Shape(3)));
+        let rpsls = Simulation::new(Always::new(spock), Frequency, 7).with_rules(Rules::rpsls());
+        let stats = rpsls.run(10).unwrap();
+        let history = stats.history().unwrap();
+        let rules = Rules::default();
+        let mut rng = Rng::new(7);
+        assert!(rules.check(Markov.choose(&rules, history, &mut rng)).is_ok());
+        assert!(rules.check(Frequency.choose(&rules, history, &mut rng)).is_ok());
+    }
+
+    #[test]
+    fn beats_relation() {
+        let rules = Rules::default();
+        let shapes = [Shape::ROCK, Shape::PAPER, Shape::SCISSORS];
+        for (shape, other) in shapes.into_iter().cartesian_product(shapes) {
+            let beats = rules.beats(shape, other);
+            assert_eq!(beats, rules.loses_to(other, shape));
+            assert!(!(beats && rules.loses_to(shape, other)));
+            assert_eq!(shape == other, !beats && !rules.loses_to(shape, other));
+            for outcome in [Outcome::Lost, Outcome::Draw, Outcome::Won] {
+                assert_eq!(outcome, rules.shape_for(other, outcome).against(other, &rules));
+            }
+        }
+        let mut sorted = [Shape::SCISSORS, Shape::ROCK, Shape::PAPER];
+        sorted.sort();
+        assert_eq!([Shape::ROCK, Shape::PAPER, Shape::SCISSORS], sorted);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(matches!(Rules::new([1, 2]), Err(RulesError::TooFew(2))));
+        assert!(matches!(Rules::new([1, 2, 3, 4]), Err(RulesError::Even(4))));
+    }
+
+    #[test]
+    fn shapes_outside_rules() {
+        let rpsls = Rules::rpsls();
+        let spock = rpsls.shape(3).unwrap();
+        assert!(matches!(Rules::default().check(spock), Err(RulesError::Shape(3))));
+        let lizard = rpsls.shape(4).unwrap();
+        assert_eq!(Outcome::Won, lizard.against(Shape::ROCK, &Rules::default()));
+        assert_eq!(Outcome::Lost, Shape::ROCK.against(lizard, &Rules::default()));
+        assert_eq!(Ok(spock), rpsls.check(spock).map_err(|_| ()));
+
+        let opponent = [("A", Shape::ROCK), ("S", spock)];
+        let decoding = Decoding::with_shapes(opponent, Decoding::SHAPES);
+        let err = Game::new(Cursor::new("A Y\nS X"), decoding.clone()).tournament().unwrap_err();
+        assert_eq!(2, err.pos);
+        assert!(matches!(err.source, RowErrorSource::Rules(RulesError::Shape(3))));
+        let game = Game::new(Cursor::new("A Y\nS X"), decoding).with_rules(rpsls);
+        assert_eq!(2, *game.tournament().unwrap().turns());
+
+        let symbols = [("A", Shape::ROCK), ("S", spock)];
+        let entrants = EntrantReader::with_symbols(Cursor::new("a A\nb A S"), symbols);
+        let err = RoundRobin::new(entrants).play().unwrap_err();
+        assert!(
+            matches!(err, TournamentError::Rules { name, source: RulesError::Shape(3) } if name == "b")
+        );
+    }
 }
@@ -1,44 +1,41 @@
 use crate::{LineReader, LinesParse, LinesParseMap, ParseControlFlow};
 
 use std::{
-    collections::HashSet,
+    collections::HashMap,
     fs::File,
     hash::{Hash, Hasher},
     io::{self, BufReader, Lines, Read},
+    iter::FusedIterator,
+    ops::{BitAnd, BitOr, RangeInclusive},
     str::FromStr,
+    sync::{Arc, LazyLock},
 };
 
 pub fn day03_file() -> io::Result<File> {
     super::input("day03")
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Priority {
     inner: u32,
 }
 
 impl Priority {
-    // PANIC: char::to_digit will panic if provided radix is greater than 36;
-    const RADIX: u32 = 36;
-    const LOWERCASE_OFFSET: u32 = 9;
-    const UPPERCASE_OFFSET: u32 = 17;
+    const LOWERCASE_START: u32 = 1;
+    const UPPERCASE_START: u32 = 27;
 
     pub fn into_inner(self) -> u32 {
         self.inner
     }
 
+    // Priority of the default table, a-z are 1 through 26 and A-Z are 27 through 52.
     pub fn new(c: char) -> Result<Self, ItemError> {
-        match (c.is_ascii_alphabetic(), c.to_digit(Self::RADIX)) {
-            (true, Some(digit)) => {
-                let inner = if c.is_ascii_lowercase() {
-                    digit - Self::LOWERCASE_OFFSET
-                } else {
-                    digit + Self::UPPERCASE_OFFSET
-                };
-                Ok(Self { inner })
-            }
-            _ => Err(ItemError(c)),
-        }
+        let inner = match c {
+            'a'..='z' => c as u32 - 'a' as u32 + Self::LOWERCASE_START,
+            'A'..='Z' => c as u32 - 'A' as u32 + Self::UPPERCASE_START,
+            _ => return Err(ItemError(c)),
+        };
+        Ok(Self { inner })
     }
 }
 
@@ -50,10 +47,115 @@ impl TryFrom<char> for Priority {
     }
 }
 
+impl From<u32> for Priority {
+    fn from(inner: u32) -> Self {
+        Self { inner }
+    }
+}
+
+static LETTERS: LazyLock<PriorityTable> = LazyLock::new(PriorityTable::letters);
+
+// Item types with their priorities, each type also owns a slot that is its
+// bit in an `ItemSet`, so a table holds at most 64 types. Clones are cheap
+// and share the same table.
+#[derive(Debug, Clone)]
+pub struct PriorityTable(Arc<Table>);
+
+#[derive(Debug, PartialEq, Eq)]
+struct Table {
+    items: Vec<(char, Priority)>,
+    ascii: [Option<u8>; 128],
+    other: HashMap<char, u8>,
+}
+
+impl PriorityTable {
+    pub const MAX: usize = u64::BITS as usize;
+
+    // Types are ranked in the same order as they are listed, starting at 1.
+    pub fn new(alphabet: &str) -> Result<Self, TableError> {
+        Self::with_weights(alphabet.chars().zip(1..))
+    }
+
+    pub fn with_weights<I>(weights: I) -> Result<Self, TableError>
+    where
+        I: IntoIterator<Item = (char, u32)>,
+    {
+        let mut table = Table { items: Vec::new(), ascii: [None; 128], other: HashMap::new() };
+        for (value, weight) in weights {
+            let slot = table.items.len();
+            if slot == Self::MAX {
+                return Err(TableError::TooMany(Self::MAX));
+            }
+            let listed = match value.is_ascii() {
+                true => table.ascii[value as usize].replace(slot as u8),
+                false => table.other.insert(value, slot as u8),
+            };
+            if listed.is_some() {
+                return Err(TableError::Duplicate(value));
+            }
+            table.items.push((value, Priority::from(weight)));
+        }
+        Ok(Self(Arc::new(table)))
+    }
+
+    // Same ranking as `Priority::new`, which is the only place it's spelled out.
+    fn letters() -> Self {
+        let letters = ('a'..='z').chain('A'..='Z');
+        // PANIC: every ASCII letter has a priority.
+        let weights = letters.map(|c| (c, Priority::new(c).expect("ASCII letter").into_inner()));
+        Self::with_weights(weights).expect("ASCII letters table")
+    }
+
+    pub fn item(&self, value: char) -> Result<Item, ItemError> {
+        let slot = match value.is_ascii() {
+            true => self.0.ascii[value as usize],
+            false => self.0.other.get(&value).copied(),
+        };
+        Ok(self.slot_item(slot.ok_or(ItemError(value))?))
+    }
+
+    pub fn priority(&self, value: char) -> Result<Priority, ItemError> {
+        Ok(self.item(value)?.priority())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.items.is_empty()
+    }
+
+    // Bits of every slot in the table.
+    fn mask(&self) -> u64 {
+        u64::MAX.checked_shr(Self::MAX as u32 - self.len() as u32).unwrap_or_default()
+    }
+
+    fn slot_item(&self, slot: u8) -> Item {
+        let (value, priority) = self.0.items[slot as usize];
+        Item { value, priority, slot }
+    }
+}
+
+impl Default for PriorityTable {
+    fn default() -> Self {
+        LETTERS.clone()
+    }
+}
+
+impl PartialEq for PriorityTable {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for PriorityTable {}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Item {
     value: char,
     priority: Priority,
+    slot: u8,
 }
 
 impl Item {
@@ -65,9 +167,13 @@ impl Item {
         self.value
     }
 
+    // Item from the default table.
     pub fn new(value: char) -> Result<Self, ItemError> {
-        let priority = Priority::try_from(value)?;
-        Ok(Self { value, priority })
+        LETTERS.item(value)
+    }
+
+    fn bit(&self) -> u64 {
+        1 << self.slot
     }
 }
 
@@ -94,65 +200,386 @@ impl TryFrom<char> for Item {
 }
 
 #[derive(Debug, Copy, Clone, thiserror::Error)]
-#[error("invalid item type: `{0}`, must be either in a-z or A-Z")]
+#[error("invalid item type: `{0}`, not found in the priority table")]
 pub struct ItemError(char);
 
-#[derive(Debug, Default)]
-struct Compartment {
-    set: HashSet<Item>,
+// Items are stored as bits at their slot in the table. Sets don't know about
+// tables, so reading items or priorities back needs the table they came from.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, item: Item) -> bool {
+        let new = !self.contains(&item);
+        self.0 |= item.bit();
+        new
+    }
+
+    pub fn contains(&self, item: &Item) -> bool {
+        self.0 & item.bit() != 0
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Slots that are not part of `table` are skipped.
+    pub fn items<'t>(&self, table: &'t PriorityTable) -> ItemSetIter<'t> {
+        ItemSetIter { bits: self.0 & table.mask(), table }
+    }
+
+    pub fn priority_sum(&self, table: &PriorityTable) -> u32 {
+        self.items(table).map(|item| item.priority().into_inner()).sum()
+    }
+}
+
+impl BitAnd for ItemSet {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl BitOr for ItemSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl Extend<Item> for ItemSet {
+    fn extend<T: IntoIterator<Item = Item>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|item| self.0 |= item.bit());
+    }
+}
+
+impl FromIterator<Item> for ItemSet {
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+// Yields items in the same order as they are listed in the table.
+#[derive(Debug, Clone)]
+pub struct ItemSetIter<'t> {
+    bits: u64,
+    table: &'t PriorityTable,
+}
+
+impl Iterator for ItemSetIter<'_> {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bits == 0 {
+            return None;
+        }
+        let slot = self.bits.trailing_zeros() as u8;
+        self.bits &= self.bits - 1;
+        Some(self.table.slot_item(slot))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bits.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ItemSetIter<'_> {}
+
+impl FusedIterator for ItemSetIter<'_> {}
+
+#[derive(Debug, Default, Clone)]
+pub struct Compartment {
+    items: Vec<Item>,
+    set: ItemSet,
 }
 
 impl Compartment {
-    fn insert_next(&mut self, value: Option<char>) -> Result<Option<()>, ItemError> {
-        let Some(value) = value else { return Ok(None) };
-        if !value.is_whitespace() {
-            self.set.insert(Item::try_from(value)?);
+    fn new(items: Vec<Item>) -> Self {
+        let set = items.iter().copied().collect();
+        Self { items, set }
+    }
+
+    // Items in the same order as they were listed.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn set(&self) -> ItemSet {
+        self.set
+    }
+
+    pub fn count(&self, item: &Item) -> usize {
+        match self.set.contains(item) {
+            true => self.items.iter().filter(|&i| i == item).count(),
+            false => 0,
         }
-        Ok(Some(()))
+    }
+
+    // Every distinct item in table order with how many times it shows up.
+    pub fn counts(&self) -> impl Iterator<Item = (Item, usize)> + '_ {
+        let mut items = self.items.clone();
+        items.sort_unstable_by_key(|item| item.slot);
+        items.dedup_by_key(|item| item.slot);
+        items.into_iter().map(|item| (item, self.count(&item)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
     }
 }
 
-#[derive(Debug, Default)]
-pub struct Rucksack(Compartment, Compartment);
+#[derive(Debug, Default, Clone)]
+pub struct Rucksack {
+    line: String,
+    first: Compartment,
+    second: Compartment,
+    table: PriorityTable,
+}
 
 impl Rucksack {
-    pub fn common(&self) -> HashSet<Item> {
-        &self.0.set & &self.1.set
+    pub fn common(&self) -> ItemSet {
+        self.first.set & self.second.set
     }
 
-    pub fn all(&self) -> HashSet<Item> {
-        &self.0.set | &self.1.set
+    pub fn all(&self) -> ItemSet {
+        self.first.set | self.second.set
+    }
+
+    pub fn with_table(s: &str, table: &PriorityTable) -> Result<Self, RucksackParseError> {
+        Ok(Self::from_line(s.parse()?, table)?)
     }
 
     pub fn common_sum(&self) -> u32 {
-        self.common().into_iter().map(|i| i.priority().into_inner()).sum()
+        self.common().priority_sum(&self.table)
+    }
+
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    // Table the items were parsed with, sets of this rucksack iterate with it.
+    pub fn table(&self) -> &PriorityTable {
+        &self.table
+    }
+
+    pub fn compartments(&self) -> (&Compartment, &Compartment) {
+        (&self.first, &self.second)
+    }
+
+    pub fn count(&self, item: &Item) -> usize {
+        self.first.count(item) + self.second.count(item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_empty() && self.second.is_empty()
+    }
+
+    // Keeps every item type in a single compartment with the fewest moves,
+    // ties are broken by the lowest relocated priority. A type kept in the
+    // first compartment has all of its copies moved out of the second one
+    // and the other way around, while both compartments keep the same size.
+    pub fn plan(&self) -> Option<MovePlan> {
+        let half = self.first.len();
+        let types = self
+            .all()
+            .items(&self.table)
+            .map(|item| (item, self.first.count(&item), self.second.count(&item)));
+        let types = types.collect::<Vec<_>>();
+        // Cheapest (moves, priority) that fills the first compartment with `size` items.
+        let mut best = vec![None; half + 1];
+        best[0] = Some((0, 0));
+        let mut kept = vec![vec![false; half + 1]; types.len()];
+        for (kept, &(item, first, second)) in kept.iter_mut().zip(&types) {
+            let priority = item.priority().into_inner();
+            let mut next: Vec<Option<(usize, u32)>> = vec![None; half + 1];
+            for (size, cost) in best.iter().enumerate() {
+                let Some((moves, relocated)) = *cost else { continue };
+                let moved = (moves + first, relocated + first as u32 * priority);
+                if next[size].map_or(true, |cost| moved < cost) {
+                    next[size] = Some(moved);
+                    kept[size] = false;
+                }
+                let size = size + first + second;
+                let moved = (moves + second, relocated + second as u32 * priority);
+                if size <= half && next[size].map_or(true, |cost| moved < cost) {
+                    next[size] = Some(moved);
+                    kept[size] = true;
+                }
+            }
+            best = next;
+        }
+        best[half]?;
+        let (mut size, mut keep) = (half, ItemSet::new());
+        for (kept, &(item, first, second)) in kept.iter().zip(&types).rev() {
+            if kept[size] {
+                keep.insert(item);
+                size -= first + second;
+            }
+        }
+        let leaving = |compartment: &Compartment, kept| {
+            let items = compartment.items.iter().copied().enumerate();
+            items.filter(|(_, item)| keep.contains(item) != kept).collect::<Vec<_>>()
+        };
+        let (first, second) = (leaving(&self.first, true), leaving(&self.second, false));
+        let swaps = first.into_iter().zip(second).map(|(first, second)| Swap { first, second });
+        Some(MovePlan { swaps: swaps.collect() })
+    }
+
+    fn from_line(line: RucksackLine, table: &PriorityTable) -> Result<Self, ItemError> {
+        let RucksackLine(line) = line;
+        let len = line.chars().count();
+        let mid = line.char_indices().nth(len / 2).map_or(line.len(), |(idx, _)| idx);
+        let (front, back) = line.split_at(mid);
+        let items = |half: &str| half.chars().map(|c| table.item(c)).collect::<Result<_, _>>();
+        let (first, second) = (items(front)?, items(back)?);
+        Ok(Self::from_items(first, second, table))
+    }
+
+    fn from_items(first: Vec<Item>, second: Vec<Item>, table: &PriorityTable) -> Self {
+        let line = first.iter().chain(&second).map(Item::value).collect();
+        let (first, second) = (Compartment::new(first), Compartment::new(second));
+        Self { line, first, second, table: table.clone() }
     }
 }
 
+// Parses items of the default table, see `Rucksack::with_table` otherwise.
 impl FromStr for Rucksack {
-    type Err = ItemError;
+    type Err = RucksackParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut rucksack = Rucksack::default();
-        let mut chars = s.trim().chars();
-        loop {
-            let next = rucksack.0.insert_next(chars.next())?;
-            let back = rucksack.1.insert_next(chars.next_back())?;
-            if let (None, None) = (next, back) {
-                break;
-            }
+        Self::with_table(s, &PriorityTable::default())
+    }
+}
+
+// A line that can be split in two compartments of equal length, items are
+// only checked against a table once the rucksack is built.
+#[derive(Debug, Clone)]
+pub struct RucksackLine(String);
+
+impl FromStr for RucksackLine {
+    type Err = RucksackParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s.trim();
+        let len = line.chars().count();
+        if let Some(pos) = line.chars().position(char::is_whitespace) {
+            return Err(Self::Err::Whitespace { len, pos });
+        }
+        if len % 2 == 1 {
+            return Err(Self::Err::Odd(len));
+        }
+        Ok(Self(line.to_owned()))
+    }
+}
+
+// Exchanges an item of the first compartment with one of the second, both
+// are indexed by their position in `Compartment::items`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Swap {
+    first: (usize, Item),
+    second: (usize, Item),
+}
+
+impl Swap {
+    pub fn first(&self) -> (usize, Item) {
+        self.first
+    }
+
+    pub fn second(&self) -> (usize, Item) {
+        self.second
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MovePlan {
+    swaps: Vec<Swap>,
+}
+
+impl MovePlan {
+    pub fn swaps(&self) -> &[Swap] {
+        &self.swaps
+    }
+
+    pub fn moves(&self) -> usize {
+        self.swaps.len() * 2
+    }
+
+    pub fn relocated(&self) -> u32 {
+        let priority = |(_, item): (usize, Item)| item.priority().into_inner();
+        self.swaps.iter().map(|swap| priority(swap.first) + priority(swap.second)).sum()
+    }
+
+    pub fn apply(&self, rucksack: &Rucksack) -> Rucksack {
+        let (mut first, mut second) = (rucksack.first.items.clone(), rucksack.second.items.clone());
+        for &Swap { first: (i, a), second: (j, b) } in &self.swaps {
+            (first[i], second[j]) = (b, a);
         }
-        Ok(rucksack)
+        Rucksack::from_items(first, second, &rucksack.table)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Reorganization(Vec<MovePlan>);
+
+impl Reorganization {
+    // One plan for each rucksack, in the order they were read.
+    pub fn plans(&self) -> &[MovePlan] {
+        &self.0
+    }
+
+    pub fn moves(&self) -> usize {
+        self.0.iter().map(MovePlan::moves).sum()
+    }
+
+    pub fn relocated(&self) -> u32 {
+        self.0.iter().map(MovePlan::relocated).sum()
     }
 }
 
 #[derive(Debug)]
-pub struct RucksackReader<R>(LineReader<R>);
+pub struct RucksackReader<R> {
+    reader: LineReader<R>,
+    table: PriorityTable,
+}
 
 impl<R: Read> RucksackReader<R> {
     pub fn new(read: R) -> Self {
         let reader = LineReader::new(read);
-        Self(reader)
+        Self { reader, table: PriorityTable::default() }
+    }
+
+    pub fn with_table(mut self, table: PriorityTable) -> Self {
+        self.table = table;
+        self
     }
 
     pub fn common_sum(self) -> Result<u32, RucksackError> {
@@ -161,6 +588,15 @@ impl<R: Read> RucksackReader<R> {
             Ok(sum)
         })
     }
+
+    pub fn plan(mut self) -> Result<Reorganization, PlanError> {
+        let mut plans = Vec::new();
+        while let Some(rucksack) = self.next() {
+            let plan = rucksack?.plan().ok_or_else(|| PlanError::Infeasible(self.reader.pos()))?;
+            plans.push(plan);
+        }
+        Ok(Reorganization(plans))
+    }
 }
 
 impl<R: Read> Iterator for RucksackReader<R> {
@@ -172,8 +608,8 @@ impl<R: Read> Iterator for RucksackReader<R> {
 }
 
 impl<R> ParseControlFlow for RucksackReader<R> {
-    type Item = Rucksack;
-    type ParseError = ItemError;
+    type Item = RucksackLine;
+    type ParseError = RucksackParseError;
 }
 
 impl<R: Read> LinesParse for RucksackReader<R> {
@@ -181,11 +617,11 @@ impl<R: Read> LinesParse for RucksackReader<R> {
     type Lines<'s> = &'s mut Lines<BufReader<R>> where Self: 's;
 
     fn lines(&mut self) -> Self::Lines<'_> {
-        self.0.lines()
+        self.reader.lines()
     }
 
     fn every_line(&mut self) {
-        self.0.advance_pos()
+        self.reader.advance_pos()
     }
 }
 
@@ -193,25 +629,37 @@ impl<R: Read> LinesParseMap for RucksackReader<R> {
     type Result = Result<Rucksack, RucksackError>;
 
     fn map(&self, res: Result<Self::Item, Self::Error>) -> Self::Result {
-        res.map_err(|err| RucksackError::new(self.0.pos(), err))
+        let res = res.and_then(|line| {
+            Rucksack::from_line(line, &self.table)
+                .map_err(|err| RucksackParseError::from(err).into())
+        });
+        res.map_err(|err| RucksackError::new(self.reader.pos(), err))
     }
 }
 
 #[derive(Debug)]
 pub struct RucksackGroup {
     id: usize,
+    size: usize,
     badge: Item,
 }
 
 impl RucksackGroup {
-    fn new(id: usize, group: Vec<Rucksack>) -> Result<Self, GroupError> {
-        let mut iter = group.iter();
-        let mut items = iter.next().ok_or_else(|| GroupError::empty(id))?.all();
-        iter.for_each(|rucksack| items = &items & &rucksack.all());
-        let mut items = items.into_iter();
+    fn new(id: usize, group: &[Rucksack]) -> Result<Self, GroupError> {
+        let (Some(first), Some(candidates)) = (group.first(), Self::candidates(group)) else {
+            return Err(GroupError::empty(id));
+        };
+        let mut items = candidates.items(first.table());
         let badge = items.next().ok_or_else(|| GroupError::missing(id))?;
         let None = items.next() else { return Err(GroupError::too_many(id)) };
-        Ok(Self { id, badge })
+        Ok(Self { id, size: group.len(), badge })
+    }
+
+    // Items shared by every rucksack of the group.
+    fn candidates(group: &[Rucksack]) -> Option<ItemSet> {
+        let mut iter = group.iter();
+        let items = iter.next()?.all();
+        Some(iter.fold(items, |items, rucksack| items & rucksack.all()))
     }
 
     pub fn badge(&self) -> Item {
@@ -221,6 +669,10 @@ impl RucksackGroup {
     pub fn id(&self) -> usize {
         self.id
     }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
 }
 
 pub struct RucksackGroupReader<R> {
@@ -235,18 +687,33 @@ impl<R: Read> RucksackGroupReader<R> {
         Self { rucksacks, size, pos: 1 }
     }
 
+    pub fn with_table(mut self, table: PriorityTable) -> Self {
+        self.rucksacks.table = table;
+        self
+    }
+
     pub fn badges_sum(self) -> Result<u32, GroupError> {
         self.into_iter().try_fold(0, |mut sum, group| {
             sum += group?.badge().priority().into_inner();
             Ok(sum)
         })
     }
-}
 
-impl<R: Read> Iterator for RucksackGroupReader<R> {
-    type Item = Result<RucksackGroup, GroupError>;
+    // Candidate badges of every group that doesn't share exactly one item.
+    pub fn diagnose(mut self) -> Result<Vec<GroupDiagnostic>, GroupError> {
+        let mut diagnostics = Vec::new();
+        while let Some(group) = self.next_group() {
+            let candidates = RucksackGroup::candidates(&group?).unwrap_or_default();
+            if candidates.len() != 1 {
+                let table = self.rucksacks.table.clone();
+                diagnostics.push(GroupDiagnostic { id: self.pos, candidates, table });
+            }
+            self.pos += 1;
+        }
+        Ok(diagnostics)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_group(&mut self) -> Option<Result<Vec<Rucksack>, GroupError>> {
         let mut group = Vec::with_capacity(self.size);
         for _ in 0..self.size {
             match self.rucksacks.next() {
@@ -256,12 +723,132 @@ impl<R: Read> Iterator for RucksackGroupReader<R> {
                 None => continue,
             }
         }
-        let group = RucksackGroup::new(self.pos, group);
+        Some(Ok(group))
+    }
+}
+
+impl<R: Read> Iterator for RucksackGroupReader<R> {
+    type Item = Result<RucksackGroup, GroupError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let group = match self.next_group()? {
+            Ok(group) => RucksackGroup::new(self.pos, &group),
+            Err(err) => return Some(Err(err)),
+        };
         self.pos += 1;
         Some(group)
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupDiagnostic {
+    id: usize,
+    candidates: ItemSet,
+    table: PriorityTable,
+}
+
+impl GroupDiagnostic {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn candidates(&self) -> ItemSet {
+        self.candidates
+    }
+
+    // Candidates as items of the table the group was read with.
+    pub fn items(&self) -> ItemSetIter<'_> {
+        self.candidates.items(&self.table)
+    }
+}
+
+// Splits consecutive rucksacks in groups that share exactly one badge,
+// without assuming every group has the same size.
+pub struct GroupFinder<R> {
+    sizes: RangeInclusive<usize>,
+    rucksacks: RucksackReader<R>,
+}
+
+impl<R: Read> GroupFinder<R> {
+    pub fn new(read: R) -> Self {
+        let rucksacks = RucksackReader::new(read);
+        Self { rucksacks, sizes: 1..=usize::MAX }
+    }
+
+    pub fn with_sizes(mut self, sizes: RangeInclusive<usize>) -> Self {
+        self.sizes = sizes;
+        self
+    }
+
+    pub fn with_table(mut self, table: PriorityTable) -> Self {
+        self.rucksacks.table = table;
+        self
+    }
+
+    // Prefers the smallest last group whenever several partitions exist.
+    pub fn partition(mut self) -> Result<GroupPartition, PartitionError> {
+        // Lines of every rucksack, so errors point at the input instead of an index.
+        let (mut rucksacks, mut lines) = (Vec::new(), Vec::new());
+        while let Some(rucksack) = self.rucksacks.next() {
+            rucksacks.push(rucksack?);
+            lines.push(self.rucksacks.reader.pos());
+        }
+        let (min, max) = ((*self.sizes.start()).max(1), *self.sizes.end());
+        // Start of the last group for every prefix of rucksacks that can be partitioned.
+        let mut starts = vec![None; rucksacks.len() + 1];
+        starts[0] = Some(0);
+        for end in 1..=rucksacks.len() {
+            let mut candidates = rucksacks[end - 1].all();
+            for start in (0..end).rev() {
+                candidates = candidates & rucksacks[start].all();
+                let size = end - start;
+                if candidates.is_empty() || size > max {
+                    break;
+                }
+                if size >= min && candidates.len() == 1 && starts[start].is_some() {
+                    starts[end] = Some(start);
+                    break;
+                }
+            }
+        }
+        if starts[rucksacks.len()].is_none() {
+            let covered = starts.iter().rposition(Option::is_some).unwrap_or_default();
+            return Err(PartitionError::Unpartitioned(lines[covered]));
+        }
+        let (mut end, mut bounds) = (rucksacks.len(), Vec::new());
+        while let Some(start) = starts[end].filter(|_| end > 0) {
+            bounds.push(start..end);
+            end = start;
+        }
+        let groups = bounds.into_iter().rev().zip(1..).map(|(bounds, id)| {
+            // PANIC: only groups that share a single badge are kept in `starts`.
+            RucksackGroup::new(id, &rucksacks[bounds]).expect("group with a single badge")
+        });
+        Ok(GroupPartition(groups.collect()))
+    }
+}
+
+#[derive(Debug)]
+pub struct GroupPartition(Vec<RucksackGroup>);
+
+impl GroupPartition {
+    pub fn groups(&self) -> &[RucksackGroup] {
+        &self.0
+    }
+
+    pub fn badges_sum(&self) -> u32 {
+        self.0.iter().map(|group| group.badge().priority().into_inner()).sum()
+    }
+}
+
+#[derive(Debug, Copy, Clone, thiserror::Error)]
+pub enum TableError {
+    #[error("table can't hold more than {0} item types")]
+    TooMany(usize),
+    #[error("item type: `{0}` is listed more than once")]
+    Duplicate(char),
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("error at line: {pos}, {source}")]
 pub struct RucksackError {
@@ -280,8 +867,36 @@ impl RucksackError {
 pub enum RucksackErrorSource {
     #[error(transparent)]
     IO(#[from] io::Error),
+    #[error(transparent)]
+    Parse(#[from] RucksackParseError),
+}
+
+#[derive(Debug, Copy, Clone, thiserror::Error)]
+pub enum RucksackParseError {
     #[error(transparent)]
     Item(#[from] ItemError),
+    #[error("rucksack of length: {0} can't be split in equal compartments")]
+    Odd(usize),
+    #[error("rucksack of length: {len} has whitespace at position: {pos}")]
+    Whitespace { len: usize, pos: usize },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlanError {
+    #[error(transparent)]
+    Rucksack(#[from] RucksackError),
+    #[error("error at line: {0}, rucksack can't keep every item type in a single compartment")]
+    Infeasible(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PartitionError {
+    #[error(transparent)]
+    Rucksack(#[from] RucksackError),
+    #[error(
+        "rucksack at line: {0} can't be grouped, only the lines before it share single badges"
+    )]
+    Unpartitioned(usize),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -325,3 +940,177 @@ pub enum GroupErrorSource {
     #[error(transparent)]
     Rucksack(#[from] RucksackError),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    const SAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    #[test]
+    fn solve_sample_common_sum() {
+        let reader = RucksackReader::new(Cursor::new(SAMPLE));
+        assert_eq!(157, reader.common_sum().unwrap());
+    }
+
+    #[test]
+    fn solve_sample_badges_sum() {
+        let groups = RucksackGroupReader::new(Cursor::new(SAMPLE), 3);
+        assert_eq!(70, groups.badges_sum().unwrap());
+    }
+
+    #[test]
+    fn rucksack_multiplicity() {
+        let rucksack = Rucksack::from_str("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        let item = |c| Item::new(c).unwrap();
+        assert_eq!("vJrwpWtwJgWrhcsFMMfFFhFp", rucksack.line());
+        assert_eq!(24, rucksack.len());
+        let (first, second) = rucksack.compartments();
+        let values = first.items().iter().map(Item::value).collect::<String>();
+        assert_eq!("vJrwpWtwJgWr", values);
+        assert_eq!((2, 0), (first.count(&item('J')), second.count(&item('J'))));
+        assert_eq!((1, 1), (first.count(&item('p')), second.count(&item('p'))));
+        assert_eq!(
+            (2, 4, 0),
+            (rucksack.count(&item('p')), rucksack.count(&item('F')), rucksack.count(&item('z')))
+        );
+        let counts = second.counts().map(|(i, n)| (i.value(), n)).collect::<Vec<_>>();
+        assert_eq!(
+            vec![('c', 1), ('f', 1), ('h', 2), ('p', 1), ('s', 1), ('F', 4), ('M', 2)],
+            counts
+        );
+        assert_eq!(16, Rucksack::from_str("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap().common_sum());
+    }
+
+    #[test]
+    fn malformed_rucksacks() {
+        const MALFORMED: &str = "abcA
+abcdA
+
+ab cA
+  abAb  
+ab1A";
+        let mut reader = RucksackReader::new(Cursor::new(MALFORMED));
+        assert_eq!(0, reader.next().unwrap().unwrap().common_sum());
+        let mut next_err = || {
+            let RucksackError { pos, source } = reader.next().unwrap().unwrap_err();
+            let RucksackErrorSource::Parse(err) = source else { panic!("expected a parse error") };
+            (pos, err)
+        };
+        assert!(matches!(next_err(), (2, RucksackParseError::Odd(5))));
+        assert!(matches!(next_err(), (4, RucksackParseError::Whitespace { len: 5, pos: 2 })));
+        assert_eq!(2, reader.next().unwrap().unwrap().common_sum());
+        let RucksackError { pos, source } = reader.next().unwrap().unwrap_err();
+        assert!(matches!(
+            (pos, source),
+            (6, RucksackErrorSource::Parse(RucksackParseError::Item(_)))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn reorganize_rucksacks() {
+        let rucksack = Rucksack::from_str("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        let plan = rucksack.plan().unwrap();
+        let (p, c) = (Item::new('p').unwrap(), Item::new('c').unwrap());
+        assert_eq!(&[Swap { first: (4, p), second: (1, c) }], plan.swaps());
+        assert_eq!((2, 16 + 3), (plan.moves(), plan.relocated()));
+        let organized = plan.apply(&rucksack);
+        assert_eq!("vJrwcWtwJgWrhpsFMMfFFhFp", organized.line());
+        assert!(organized.common().is_empty());
+
+        let reorganization = RucksackReader::new(Cursor::new(SAMPLE)).plan().unwrap();
+        let moves = reorganization.plans().iter().map(MovePlan::moves).collect::<Vec<_>>();
+        assert_eq!(vec![2, 4, 2, 4, 4, 2], moves);
+        assert_eq!((18, 345), (reorganization.moves(), reorganization.relocated()));
+        let err = RucksackReader::new(Cursor::new("abAB\naabaaa")).plan().unwrap_err();
+        assert!(matches!(err, PlanError::Infeasible(2)));
+    }
+
+    #[test]
+    fn discover_groups() {
+        let finder = GroupFinder::new(Cursor::new(SAMPLE)).with_sizes(3..=3);
+        let partition = finder.partition().unwrap();
+        let badges = partition.groups().iter().map(|g| (g.id(), g.size(), g.badge().value()));
+        assert_eq!(vec![(1, 3, 'r'), (2, 3, 'Z')], badges.collect::<Vec<_>>());
+        assert_eq!(70, partition.badges_sum());
+
+        let partition = GroupFinder::new(Cursor::new(SAMPLE)).partition().unwrap();
+        assert_eq!(2, partition.groups().len());
+        let finder = GroupFinder::new(Cursor::new("abcd\naefg\nzhij\nzklm\nznop"));
+        let partition = finder.partition().unwrap();
+        let badges = partition.groups().iter().map(|g| (g.size(), g.badge().value()));
+        assert_eq!(vec![(2, 'a'), (3, 'z')], badges.collect::<Vec<_>>());
+        let err = GroupFinder::new(Cursor::new(SAMPLE)).with_sizes(4..=6).partition();
+        assert!(matches!(err, Err(PartitionError::Unpartitioned(1))));
+        let err = GroupFinder::new(Cursor::new("abcd\n\naefg\n\nzhij\nqrst")).partition();
+        assert!(matches!(err, Err(PartitionError::Unpartitioned(5))));
+
+        let diagnostics = RucksackGroupReader::new(Cursor::new(SAMPLE), 2).diagnose().unwrap();
+        let candidates = |d: &GroupDiagnostic| d.items().map(|i| i.value()).collect();
+        let diagnostics = diagnostics.iter().map(|d| (d.id(), candidates(d)));
+        assert_eq!(
+            vec![(1, "frsFM".into()), (2, "qvwBT".into()), (3, "GJZ".into())],
+            diagnostics.collect::<Vec<(usize, String)>>()
+        );
+    }
+
+    #[test]
+    fn priority_tables() {
+        let letters = ('a'..='z').chain('A'..='Z').collect::<String>();
+        assert_eq!(PriorityTable::default(), PriorityTable::new(&letters).unwrap());
+        assert_eq!(52, Priority::new('Z').unwrap().into_inner());
+
+        let digits = PriorityTable::new("0123456789").unwrap();
+        let reader = RucksackReader::new(Cursor::new("1234\n0550\n99"));
+        assert_eq!(7 + 10, reader.with_table(digits.clone()).common_sum().unwrap());
+        let groups = RucksackGroupReader::new(Cursor::new("0011\n0022\n0033"), 3);
+        assert_eq!(1, groups.with_table(digits).badges_sum().unwrap());
+
+        let umlauts = PriorityTable::with_weights([('ä', 5), ('ö', 7), ('ß', 11)]).unwrap();
+        let rucksack = Rucksack::with_table("äößä", &umlauts).unwrap();
+        assert_eq!((5, 4), (rucksack.common_sum(), rucksack.len()));
+        let err = Rucksack::with_table("abab", &umlauts).unwrap_err();
+        assert!(matches!(err, RucksackParseError::Item(ItemError('a'))));
+
+        assert!(matches!(PriorityTable::new("abca"), Err(TableError::Duplicate('a'))));
+        let many = (0..=64).filter_map(|n| char::from_u32(0x100 + n)).collect::<String>();
+        assert!(matches!(PriorityTable::new(&many), Err(TableError::TooMany(64))));
+        let weights = PriorityTable::with_weights([('a', 100), ('b', 100), ('c', 0)]).unwrap();
+        let rucksack = Rucksack::with_table("abcbac", &weights).unwrap();
+        assert_eq!(200, rucksack.common_sum());
+        let values = rucksack.common().items(&weights).map(|i| i.value()).collect::<String>();
+        assert_eq!("abc", values);
+        let digits = PriorityTable::new("0123456789").unwrap();
+        let nines = Rucksack::with_table("9999", &digits).unwrap();
+        assert_eq!(0, nines.common().items(&weights).count());
+        let groups = GroupFinder::new(Cursor::new("0011\n0022")).with_table(digits);
+        let partition = groups.partition().unwrap();
+        let badges = partition.groups().iter().map(|g| g.badge().value());
+        assert_eq!(vec!['0'], badges.collect::<Vec<_>>());
+
+        fn assert_send<T: Send>() {}
+        assert_send::<Rucksack>();
+        assert_send::<RucksackReader<Cursor<&str>>>();
+    }
+
+    #[test]
+    fn item_set_operations() {
+        let set = |s: &str| s.chars().map(|c| Item::new(c).unwrap()).collect::<ItemSet>();
+        let (abc, bcz) = (set("aBcZ"), set("BZzZ"));
+        assert_eq!((4, 3), (abc.len(), bcz.len()));
+        assert_eq!(set("BZ"), abc & bcz);
+        assert_eq!(set("aBcZz"), abc | bcz);
+        let letters = PriorityTable::default();
+        let values = (abc | bcz).items(&letters).map(|i| i.value()).collect::<String>();
+        assert_eq!("aczBZ", values);
+        assert!(ItemSet::new().is_empty());
+        assert_eq!(3 + 28 + 52, set("cBZ").priority_sum(&letters));
+    }
+}
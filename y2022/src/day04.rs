@@ -1,10 +1,13 @@
-use crate::{LineReader, LinesParse, LinesParseMap, ParseControlFlow};
+use crate::{
+    interval::{Interval, IntervalSet, IntervalTree},
+    LineReader, LinesParse, LinesParseMap, ParseControlFlow,
+};
 
 use std::{
+    fmt::{self, Display},
     fs::File,
-    io::{self, BufReader, Lines, Read},
+    io::{self, BufReader, Lines, Read, Write},
     num::ParseIntError,
-    ops::RangeInclusive,
     str::FromStr,
 };
 
@@ -16,22 +19,28 @@ type SectionId = u64;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Sections {
-    range: RangeInclusive<SectionId>,
+    range: Interval<SectionId>,
 }
 
 impl Sections {
     const DELIMETER: char = '-';
 
     pub fn contains(&self, other: &Self) -> bool {
-        let start = self.range.start() <= other.range.start();
-        let end = self.range.end() >= other.range.end();
-        start && end
+        self.range.covers(&other.range)
     }
 
     pub fn overlaps(&self, other: &Self) -> bool {
-        let start = self.range.start() <= other.range.end();
-        let end = self.range.end() >= other.range.start();
-        start && end
+        self.range.overlaps(&other.range)
+    }
+
+    pub fn interval(&self) -> Interval<SectionId> {
+        self.range
+    }
+}
+
+impl Display for Sections {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.range.start(), Self::DELIMETER, self.range.end())
     }
 }
 
@@ -43,22 +52,135 @@ impl FromStr for Sections {
         let start = bounds.next().ok_or(Self::Err::START)?.trim().parse()?;
         let end = bounds.next().ok_or(Self::Err::END)?.trim().parse()?;
         let None = bounds.next() else { return Err(Self::Err::Trailing) };
-        Ok(Self { range: RangeInclusive::new(start, end) })
+        if start > end {
+            return Err(Self::Err::Reversed { start, end });
+        }
+        Ok(Self { range: Interval::new(start, end) })
     }
 }
 
+// A group of exactly two assignments.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SectionsPair(Sections, Sections);
+pub struct SectionsPair(SectionsGroup);
 
 impl SectionsPair {
-    const DELIMETER: char = ',';
+    const LEN: usize = 2;
 
     pub fn contains(&self) -> bool {
-        self.0.contains(&self.1) || self.1.contains(&self.0)
+        self.0.any_contains()
     }
 
     pub fn overlaps(&self) -> bool {
-        self.0.overlaps(&self.1) || self.1.overlaps(&self.0)
+        self.0.common().is_some()
+    }
+
+    pub fn sections(&self) -> (&Sections, &Sections) {
+        (&self.0 .0[0], &self.0 .0[1])
+    }
+
+    pub fn group(&self) -> &SectionsGroup {
+        &self.0
+    }
+
+    pub fn relation(&self) -> Relation {
+        let (sec0, sec1) = self.sections();
+        let (range0, range1) = (sec0.range, sec1.range);
+        match (sec0.contains(sec1), sec1.contains(sec0)) {
+            (true, true) => Relation::Equal,
+            (true, false) => Relation::FirstContainsSecond,
+            (false, true) => Relation::SecondContainsFirst,
+            _ if range0.overlaps(&range1) => Relation::Overlapping,
+            _ if range0.is_adjacent(&range1) => Relation::Adjacent,
+            _ => Relation::Disjoint,
+        }
+    }
+
+    // Number of sections assigned to both elves.
+    pub fn overlap(&self) -> u128 {
+        self.0.common().map_or(0, |common| common.len())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Relation {
+    Disjoint,
+    Adjacent,
+    Overlapping,
+    FirstContainsSecond,
+    SecondContainsFirst,
+    Equal,
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let relation = match self {
+            Self::Disjoint => "disjoint",
+            Self::Adjacent => "adjacent",
+            Self::Overlapping => "overlapping",
+            Self::FirstContainsSecond => "first-contains-second",
+            Self::SecondContainsFirst => "second-contains-first",
+            Self::Equal => "equal",
+        };
+        f.write_str(relation)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairReport {
+    line: usize,
+    pair: SectionsPair,
+    relation: Relation,
+    overlap: u128,
+}
+
+impl PairReport {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn pair(&self) -> &SectionsPair {
+        &self.pair
+    }
+
+    pub fn relation(&self) -> Relation {
+        self.relation
+    }
+
+    pub fn overlap(&self) -> u128 {
+        self.overlap
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report(Vec<PairReport>);
+
+impl Report {
+    const CSV_HEADER: &str = "line,first,second,relation,overlap";
+
+    pub fn pairs(&self) -> &[PairReport] {
+        &self.0
+    }
+
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", Self::CSV_HEADER)?;
+        for PairReport { line, pair, relation, overlap } in &self.0 {
+            let (sec0, sec1) = pair.sections();
+            writeln!(writer, "{line},{sec0},{sec1},{relation},{overlap}")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<SectionsGroup> for SectionsPair {
+    type Error = SectionsPairError;
+
+    fn try_from(group: SectionsGroup) -> Result<Self, Self::Error> {
+        match group.len() {
+            0 => Err(Self::Error::FIRST),
+            1 => Err(Self::Error::SECOND),
+            Self::LEN => Ok(Self(group)),
+            _ => Err(Self::Error::Trailing),
+        }
     }
 }
 
@@ -66,21 +188,122 @@ impl FromStr for SectionsPair {
     type Err = SectionsPairError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut sections = s.trim().split(Self::DELIMETER);
-        let sec0 = sections.next().ok_or(Self::Err::FIRST)?.trim().parse()?;
-        let sec1 = sections.next().ok_or(Self::Err::SECOND)?.trim().parse()?;
-        let None = sections.next() else { return Err(Self::Err::Trailing) };
-        Ok(Self(sec0, sec1))
+        Self::try_from(s.parse::<SectionsGroup>()?)
+    }
+}
+
+// Any number of comma separated assignments on the same line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionsGroup(Vec<Sections>);
+
+impl SectionsGroup {
+    const DELIMETER: char = ',';
+
+    pub fn sections(&self) -> &[Sections] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Some range fully contains another one of the group.
+    pub fn any_contains(&self) -> bool {
+        let mut pairs = self.0.iter().enumerate().flat_map(|(i, sec0)| {
+            self.0
+                .iter()
+                .enumerate()
+                .filter(move |&(j, _)| i != j)
+                .map(move |(_, sec1)| (sec0, sec1))
+        });
+        pairs.any(|(sec0, sec1)| sec0.contains(sec1))
+    }
+
+    // Sections assigned to every range of the group.
+    pub fn common(&self) -> Option<Interval<SectionId>> {
+        let mut iter = self.0.iter().map(Sections::interval);
+        let first = iter.next()?;
+        let common = iter.fold(first, |common, range| common.intersection(&range));
+        (!common.is_empty()).then_some(common)
+    }
+
+    pub fn overlap_matrix(&self) -> Vec<Vec<bool>> {
+        let row = |sec0: &Sections| self.0.iter().map(|sec1| sec0.overlaps(sec1)).collect();
+        self.0.iter().map(row).collect()
+    }
+}
+
+impl From<SectionsPair> for SectionsGroup {
+    fn from(pair: SectionsPair) -> Self {
+        pair.0
+    }
+}
+
+impl FromStr for SectionsGroup {
+    type Err = SectionsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sections = s.trim().split(Self::DELIMETER).map(str::parse);
+        Ok(Self(sections.collect::<Result<_, _>>()?))
+    }
+}
+
+// Identifies an elf by the line of its group and its place within the group.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Elf {
+    line: usize,
+    idx: usize,
+}
+
+impl Elf {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn idx(&self) -> usize {
+        self.idx
     }
 }
 
+// Every elf's sections, for queries that cross the pairs' lines.
+#[derive(Debug, Clone)]
+pub struct Assignments(IntervalTree<SectionId, Elf>);
+
+impl Assignments {
+    pub fn elves_at(&self, section: SectionId) -> Vec<Elf> {
+        self.0.stabbing(section).into_iter().map(|&(_, elf)| elf).collect()
+    }
+
+    pub fn overlapping(&self, sections: &Sections) -> Vec<(Sections, Elf)> {
+        let entries = self.0.overlapping(sections.range).into_iter();
+        entries.map(|&(range, elf)| (Sections { range }, elf)).collect()
+    }
+
+    // Section with the most elves assigned to it, along with how many.
+    pub fn busiest(&self) -> Option<(SectionId, usize)> {
+        self.0.max_overlap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// Reads groups, rejecting lines that don't have exactly two assignments.
 #[derive(Debug)]
-pub struct SectionsPairReader<R>(LineReader<R>);
+pub struct SectionsPairReader<R>(SectionsGroupReader<R>);
 
 impl<R: Read> SectionsPairReader<R> {
     pub fn new(read: R) -> Self {
-        let reader = LineReader::new(read);
-        Self(reader)
+        Self(SectionsGroupReader::new(read))
     }
 
     pub fn contained_pairs(self) -> Result<usize, PairReadError> {
@@ -96,23 +319,94 @@ impl<R: Read> SectionsPairReader<R> {
             Ok(count)
         })
     }
+
+    // Distinct sections assigned to at least one elf.
+    pub fn covered_sections(self) -> Result<u128, PairReadError> {
+        let mut covered = IntervalSet::new();
+        for pair in self {
+            covered.extend(pair?.group().sections().iter().map(Sections::interval));
+        }
+        Ok(covered.len())
+    }
+
+    pub fn report(mut self) -> Result<Report, PairReadError> {
+        let mut pairs = Vec::new();
+        while let Some(pair) = self.next() {
+            let pair = pair?;
+            let (relation, overlap) = (pair.relation(), pair.overlap());
+            pairs.push(PairReport { line: self.0.pos(), pair, relation, overlap });
+        }
+        Ok(Report(pairs))
+    }
+
+    pub fn assignments(mut self) -> Result<Assignments, PairReadError> {
+        let mut elves = Vec::new();
+        while let Some(pair) = self.next() {
+            let line = self.0.pos();
+            let sections = pair?.0 .0.into_iter().enumerate();
+            elves.extend(sections.map(|(idx, sections)| (sections.range, Elf { line, idx })));
+        }
+        Ok(Assignments(IntervalTree::new(elves)))
+    }
 }
 
 impl<R: Read> Iterator for SectionsPairReader<R> {
     type Item = Result<SectionsPair, PairReadError>;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        let group = self.0.next()?;
+        let pair = |group: SectionsGroup| {
+            let pair = SectionsPair::try_from(group);
+            pair.map_err(|err| PairReadError::new(self.0.pos(), err.into()))
+        };
+        Some(group.map_err(PairReadError::from).and_then(pair))
+    }
+}
+
+#[derive(Debug)]
+pub struct SectionsGroupReader<R>(LineReader<R>);
+
+impl<R: Read> SectionsGroupReader<R> {
+    pub fn new(read: R) -> Self {
+        let reader = LineReader::new(read);
+        Self(reader)
+    }
+
+    fn pos(&self) -> usize {
+        self.0.pos()
+    }
+
+    pub fn contained_groups(self) -> Result<usize, GroupReadError> {
+        self.into_iter().try_fold(0, |mut count, group| {
+            group?.any_contains().then(|| count += 1);
+            Ok(count)
+        })
+    }
+
+    // Groups where every range shares at least one section.
+    pub fn overlaped_groups(self) -> Result<usize, GroupReadError> {
+        self.into_iter().try_fold(0, |mut count, group| {
+            group?.common().is_some().then(|| count += 1);
+            Ok(count)
+        })
+    }
+}
+
+impl<R: Read> Iterator for SectionsGroupReader<R> {
+    type Item = Result<SectionsGroup, GroupReadError>;
+
     fn next(&mut self) -> Option<Self::Item> {
         self.parse_next_map()
     }
 }
 
-impl<R> ParseControlFlow for SectionsPairReader<R> {
-    type Item = SectionsPair;
-    type ParseError = SectionsPairError;
+impl<R> ParseControlFlow for SectionsGroupReader<R> {
+    type Item = SectionsGroup;
+    type ParseError = SectionsError;
 }
 
-impl<R: Read> LinesParse for SectionsPairReader<R> {
-    type Error = PairReadErrorSource;
+impl<R: Read> LinesParse for SectionsGroupReader<R> {
+    type Error = GroupReadErrorSource;
     type Lines<'s> = &'s mut Lines<BufReader<R>> where Self: 's;
 
     fn lines(&mut self) -> Self::Lines<'_> {
@@ -124,11 +418,11 @@ impl<R: Read> LinesParse for SectionsPairReader<R> {
     }
 }
 
-impl<R: Read> LinesParseMap for SectionsPairReader<R> {
-    type Result = Result<Self::Item, PairReadError>;
+impl<R: Read> LinesParseMap for SectionsGroupReader<R> {
+    type Result = Result<Self::Item, GroupReadError>;
 
     fn map(&self, res: Result<Self::Item, Self::Error>) -> Self::Result {
-        res.map_err(|err| PairReadError::new(self.0.pos(), err))
+        res.map_err(|err| GroupReadError::new(self.0.pos(), err))
     }
 }
 
@@ -146,6 +440,16 @@ impl PairReadError {
     }
 }
 
+impl From<GroupReadError> for PairReadError {
+    fn from(GroupReadError { pos, source }: GroupReadError) -> Self {
+        let source = match source {
+            GroupReadErrorSource::IO(err) => PairReadErrorSource::IO(err),
+            GroupReadErrorSource::Parse(err) => PairReadErrorSource::Parse(err.into()),
+        };
+        Self { pos, source }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum PairReadErrorSource {
     #[error(transparent)]
@@ -154,6 +458,28 @@ pub enum PairReadErrorSource {
     Parse(#[from] SectionsPairError),
 }
 
+#[derive(Debug, thiserror::Error)]
+#[error("error at line: {pos}, {source}")]
+pub struct GroupReadError {
+    pos: usize,
+    #[source]
+    source: GroupReadErrorSource,
+}
+
+impl GroupReadError {
+    fn new(pos: usize, source: GroupReadErrorSource) -> Self {
+        Self { pos, source }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GroupReadErrorSource {
+    #[error(transparent)]
+    IO(#[from] io::Error),
+    #[error(transparent)]
+    Parse(#[from] SectionsError),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SectionsPairError {
     #[error(transparent)]
@@ -185,6 +511,8 @@ pub enum SectionsError {
     Trailing,
     #[error(transparent)]
     Missing(MissingSections),
+    #[error("section's range start: {start} is greater than its end: {end}")]
+    Reversed { start: SectionId, end: SectionId },
 }
 
 impl SectionsError {
@@ -199,3 +527,152 @@ pub enum MissingSections {
     #[error("missing section's range end")]
     End,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    const SAMPLE: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8";
+
+    #[test]
+    fn solve_sample_pairs() {
+        let reader = SectionsPairReader::new(Cursor::new(SAMPLE));
+        assert_eq!(2, reader.contained_pairs().unwrap());
+        let reader = SectionsPairReader::new(Cursor::new(SAMPLE));
+        assert_eq!(4, reader.overlaped_pairs().unwrap());
+    }
+
+    #[test]
+    fn section_bounds() {
+        const BOUNDS: &str = "2-8,8-2
+3-3,3-3
+0-18446744073709551615,18446744073709551615-18446744073709551615
+1-18446744073709551616,1-2";
+        let mut reader = SectionsPairReader::new(Cursor::new(BOUNDS));
+        let mut next_err = || {
+            let PairReadError { pos, source } = reader.next().unwrap().unwrap_err();
+            let PairReadErrorSource::Parse(SectionsPairError::Section(err)) = source else {
+                panic!("expected a sections error")
+            };
+            (pos, err)
+        };
+        assert!(matches!(next_err(), (1, SectionsError::Reversed { start: 8, end: 2 })));
+        let pair = reader.next().unwrap().unwrap();
+        assert!(pair.contains() && pair.overlaps());
+        assert_eq!(1, pair.sections().0.interval().len());
+        let pair = reader.next().unwrap().unwrap();
+        assert!(pair.contains() && pair.overlaps());
+        assert_eq!(1 << 64, pair.sections().0.interval().len());
+        let PairReadError { pos, source } = reader.next().unwrap().unwrap_err();
+        assert_eq!(4, pos);
+        assert!(matches!(
+            source,
+            PairReadErrorSource::Parse(SectionsPairError::Section(SectionsError::Parse(_)))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn sections_groups() {
+        let reader = SectionsGroupReader::new(Cursor::new(SAMPLE));
+        assert_eq!(2, reader.contained_groups().unwrap());
+        let reader = SectionsGroupReader::new(Cursor::new(SAMPLE));
+        assert_eq!(4, reader.overlaped_groups().unwrap());
+
+        let group = SectionsGroup::from_str("1-5,4-9,5-7").unwrap();
+        assert_eq!(3, group.len());
+        assert!(group.any_contains());
+        assert_eq!(Some(Interval::new(5, 5)), group.common());
+        let matrix = vec![vec![true, true, true], vec![true, true, true], vec![true, true, true]];
+        assert_eq!(matrix, group.overlap_matrix());
+        let group = SectionsGroup::from_str("1-3,3-4,4-6,8-8").unwrap();
+        assert!(!group.any_contains());
+        assert_eq!(None, group.common());
+        assert_eq!(
+            vec![
+                vec![true, true, false, false],
+                vec![true, true, true, false],
+                vec![false, true, true, false],
+                vec![false, false, false, true],
+            ],
+            group.overlap_matrix()
+        );
+        let pair = SectionsPair::from_str("2-8,3-7").unwrap();
+        assert_eq!(pair.contains(), SectionsGroup::from(pair).any_contains());
+        let mut reader = SectionsPairReader::new(Cursor::new("1-2\n1-2,3-4\n1-2,3-4,5-6"));
+        let mut next_err = || {
+            let PairReadError { pos, source } = reader.next().unwrap().unwrap_err();
+            let PairReadErrorSource::Parse(err) = source else { panic!("expected a pair error") };
+            (pos, err)
+        };
+        assert!(matches!(next_err(), (1, SectionsPairError::Missing(MissingPair::Second))));
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next().unwrap(), Err(PairReadError { pos: 3, .. })));
+
+        let mut reader = SectionsGroupReader::new(Cursor::new("1-2\n1-2,,3-4"));
+        assert_eq!(1, reader.next().unwrap().unwrap().len());
+        let GroupReadError { pos, source } = reader.next().unwrap().unwrap_err();
+        assert!(matches!((pos, source), (2, GroupReadErrorSource::Parse(SectionsError::Parse(_)))));
+    }
+
+    #[test]
+    fn cross_line_assignments() {
+        let assignments = SectionsPairReader::new(Cursor::new(SAMPLE)).assignments().unwrap();
+        assert_eq!(12, assignments.len());
+        let elves = assignments.elves_at(6).into_iter().map(|elf| (elf.line(), elf.idx()));
+        let mut elves = elves.collect::<Vec<_>>();
+        elves.sort_unstable();
+        assert_eq!(vec![(1, 1), (3, 0), (4, 0), (4, 1), (5, 0), (5, 1), (6, 0), (6, 1)], elves);
+        assert!(assignments.elves_at(1).is_empty());
+        let overlapping = assignments.overlapping(&Sections::from_str("9-12").unwrap());
+        let overlapping = overlapping.into_iter().map(|(sections, elf)| {
+            let range = sections.interval();
+            ((range.start(), range.end()), (elf.line(), elf.idx()))
+        });
+        assert_eq!(vec![((7, 9), (3, 1))], overlapping.collect::<Vec<_>>());
+        assert_eq!(Some((6, 8)), assignments.busiest());
+    }
+
+    #[test]
+    fn pairs_report() {
+        let report = SectionsPairReader::new(Cursor::new(SAMPLE)).report().unwrap();
+        let relations = report.pairs().iter().map(|pair| (pair.relation(), pair.overlap()));
+        assert_eq!(
+            vec![
+                (Relation::Disjoint, 0),
+                (Relation::Adjacent, 0),
+                (Relation::Overlapping, 1),
+                (Relation::FirstContainsSecond, 5),
+                (Relation::SecondContainsFirst, 1),
+                (Relation::Overlapping, 3),
+            ],
+            relations.collect::<Vec<_>>()
+        );
+        assert_eq!(Relation::Equal, SectionsPair::from_str("3-5,3-5").unwrap().relation());
+
+        const CSV: &str = "line,first,second,relation,overlap
+1,2-4,6-8,disjoint,0
+2,2-3,4-5,adjacent,0
+4,5-7,7-9,overlapping,1
+5,2-8,3-7,first-contains-second,5
+";
+        let reader = SectionsPairReader::new(Cursor::new("2-4,6-8\n2-3, 4-5\n\n5-7,7-9\n2-8,3-7"));
+        let mut csv = Vec::new();
+        reader.report().unwrap().write_csv(&mut csv).unwrap();
+        assert_eq!(CSV, String::from_utf8(csv).unwrap());
+    }
+
+    #[test]
+    fn covered_sections() {
+        let reader = SectionsPairReader::new(Cursor::new(SAMPLE));
+        assert_eq!(8, reader.covered_sections().unwrap());
+        let reader = SectionsPairReader::new(Cursor::new("1-2,5-6\n10-10,3-3"));
+        assert_eq!(6, reader.covered_sections().unwrap());
+    }
+}
@@ -1,15 +1,17 @@
-use crate::{LinePeeker, LinesParseIfOk, ParseControlFlow, PeekableLines};
+use crate::{LinePeeker, LinesParse, LinesParseMap, ParseControlFlow, PeekableLines};
 
 use std::{
     collections::{hash_map::Entry, HashMap},
-    fmt::{self, Debug, Formatter},
+    env,
+    fmt::{self, Debug, Display, Formatter},
     fs::File,
-    io::{self, BufReader, Lines, Read},
-    iter::{self, Peekable},
-    marker::PhantomData,
-    num::ParseIntError,
-    ops::ControlFlow::{self, Break, Continue},
+    io::{self, Read, Write},
+    iter,
+    num::{NonZeroUsize, ParseIntError},
+    ops::ControlFlow::{self, Break},
     str::FromStr,
+    thread,
+    time::Duration,
     vec,
 };
 
@@ -17,10 +19,43 @@ pub fn day05_file() -> io::Result<File> {
     super::input("day05")
 }
 
-pub fn drawing<R: Read>(input: R) -> (Platform, Lifts) {
-    let (platform, lines) = Platform::read(input);
-    let (lifts, _) = LiftPeeker::from(lines).lifts();
-    (platform, lifts)
+pub fn drawing<R: Read>(input: R) -> Result<(Platform, Lifts), DrawingError> {
+    let (platform, lines) = Platform::read(input)?;
+    let (lifts, _) = LiftPeeker::from(lines).lifts()?;
+    Ok((platform, lifts))
+}
+
+// Milliseconds to wait between frames, animations are disabled if unset.
+const ANIMATION_DELAY: &str = "DAY05_DELAY_MS";
+
+pub fn animation_delay() -> Result<Option<Duration>, ParseIntError> {
+    let Some(delay) = env::var_os(ANIMATION_DELAY) else { return Ok(None) };
+    let millis = delay.to_string_lossy().trim().parse()?;
+    Ok(Some(Duration::from_millis(millis)))
+}
+
+// Redraws the whole platform after every step, the terminal is cleared
+// in between each frame.
+pub fn animate<C: Crane, W: Write>(
+    mut steps: Steps<C>,
+    mut out: W,
+    delay: Duration,
+) -> Result<(), AnimationError> {
+    const CLEAR: &str = "\x1b[2J\x1b[H";
+    write!(out, "{CLEAR}{}\n\n", steps.platform())?;
+    while let Some(step) = steps.next() {
+        let step = step?;
+        thread::sleep(delay);
+        write!(
+            out,
+            "{CLEAR}{}\n\nline {}: {}\n",
+            steps.platform(),
+            step.lift().pos(),
+            step.lift()
+        )?;
+        out.flush()?;
+    }
+    Ok(())
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -47,6 +82,12 @@ impl From<char> for Crate {
     }
 }
 
+impl Display for Crate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", Self::PREFIX, self.value, Self::SUFFIX)
+    }
+}
+
 impl From<&Crate> for char {
     fn from(krate: &Crate) -> Self {
         krate.into_inner()
@@ -66,30 +107,22 @@ impl FromStr for Crate {
     }
 }
 
+// Crates along with the column of their identifier, stacks are matched by
+// the label drawn under that column.
 #[derive(Debug, Default)]
 struct CrateRow {
-    row: Vec<Option<Crate>>,
+    row: Vec<(usize, Crate)>,
+    pos: usize,
 }
 
 impl CrateRow {
-    const CHUNK_LEN: usize = Crate::LEN + 1;
-
-    fn try_push<I>(&mut self, chunk: I) -> Result<(), CrateParseError>
-    where
-        I: IntoIterator<Item = char>,
-    {
-        let slot = String::from_iter(chunk);
-        let slot = slot.trim();
-        match slot.is_empty() {
-            true => self.row.push(None),
-            false => self.row.push(Some(Crate::from_str(slot)?)),
-        }
-        Ok(())
+    fn starts(line: &str) -> bool {
+        line.trim_start().starts_with(Crate::PREFIX)
     }
 }
 
 impl IntoIterator for CrateRow {
-    type Item = Option<Crate>;
+    type Item = (usize, Crate);
     type IntoIter = CrateRowIntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -99,7 +132,7 @@ impl IntoIterator for CrateRow {
 
 #[derive(Debug)]
 struct CrateRowIntoIter {
-    iter: vec::IntoIter<Option<Crate>>,
+    iter: vec::IntoIter<(usize, Crate)>,
 }
 
 impl CrateRowIntoIter {
@@ -110,7 +143,7 @@ impl CrateRowIntoIter {
 }
 
 impl Iterator for CrateRowIntoIter {
-    type Item = Option<Crate>;
+    type Item = (usize, Crate);
 
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next()
@@ -122,13 +155,21 @@ impl FromStr for CrateRow {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut this = Self::default();
-        let mut chunks = s.chars().array_chunks::<{ Self::CHUNK_LEN }>();
-        chunks.by_ref().try_for_each(|chunk| this.try_push(chunk))?;
-        chunks.into_remainder().map_or(Ok(()), |rem| this.try_push(rem))?;
+        let mut chars = s.chars().enumerate();
+        while let Some((col, c)) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let cell = chars.by_ref().take(Crate::LEN - 1).map(|(_, c)| c);
+            let krate = iter::once(c).chain(cell).collect::<String>().parse()?;
+            this.row.push((col + 1, krate));
+        }
         Ok(this)
     }
 }
 
+// Lines that start with a crate are crate rows, the first line that doesn't
+// is left for the label row.
 #[derive(Debug)]
 struct CrateRowPeeker<R: Read>(LinePeeker<R>);
 
@@ -137,7 +178,7 @@ impl<R: Read> CrateRowPeeker<R> {
         self.0
     }
 
-    pub fn rows(self) -> (CrateRows, LinePeeker<R>) {
+    pub fn rows(self) -> Result<(CrateRows, LinePeeker<R>), CrateRowReaderError> {
         CrateRows::new(self)
     }
 }
@@ -148,38 +189,18 @@ impl<R: Read> From<LinePeeker<R>> for CrateRowPeeker<R> {
     }
 }
 
-impl<R: Read> ParseControlFlow for CrateRowPeeker<R> {
-    type Item = CrateRow;
-    type ParseError = CrateParseError;
-
-    fn parse(s: &str) -> ControlFlow<Result<Self::Item, Self::ParseError>, ()> {
-        // We can't trim lines before parsing, that would exclude trailing
-        // and leading empty slots, which will be represented as None.
-        match s.trim().is_empty() {
-            true => Continue(()),
-            false => Break(s.parse()),
-        }
-    }
-}
-
-impl<R: Read> LinesParseIfOk for CrateRowPeeker<R> {
-    type InnerIter = Lines<BufReader<R>>;
-    type Peekable<'s> = &'s mut Peekable<Self::InnerIter> where Self: 's;
-
-    fn peekable(&mut self) -> Self::Peekable<'_> {
-        self.0.peekable()
-    }
-
-    fn every_line(&mut self) {
-        self.0.advance_pos()
-    }
-}
-
 impl<R: Read> Iterator for CrateRowPeeker<R> {
-    type Item = CrateRow;
+    type Item = Result<CrateRow, CrateRowReaderError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.parse_next_if_ok()
+        // Read errors are taken as well, so that they are reported.
+        let is_row = |line: &io::Result<String>| line.as_deref().map_or(true, CrateRow::starts);
+        let line = self.0.peekable().next_if(is_row)?;
+        self.0.advance_pos();
+        let pos = self.0.pos();
+        let row = line.map_err(CrateRowReaderErrorSource::from).and_then(|line| Ok(line.parse()?));
+        let row = row.map(|row| CrateRow { pos, ..row });
+        Some(row.map_err(|err| CrateRowReaderError::new(pos, err)))
     }
 }
 
@@ -189,10 +210,12 @@ struct CrateRows {
 }
 
 impl CrateRows {
-    fn new<R: Read>(mut peeker: CrateRowPeeker<R>) -> (CrateRows, LinePeeker<R>) {
-        let rows = (&mut peeker).collect();
+    fn new<R: Read>(
+        mut peeker: CrateRowPeeker<R>,
+    ) -> Result<(CrateRows, LinePeeker<R>), CrateRowReaderError> {
+        let rows = (&mut peeker).collect::<Result<_, _>>()?;
         let peeker = peeker.into_inner();
-        (Self { rows }, peeker)
+        Ok((Self { rows }, peeker))
     }
 }
 
@@ -206,11 +229,16 @@ impl Iterator for CrateRows {
 }
 
 #[derive(Debug, Default, Clone)]
-struct Stack {
+pub struct Stack {
     stack: Vec<Crate>,
 }
 
 impl Stack {
+    // Crates from the bottom of the stack up to its top.
+    pub fn crates(&self) -> &[Crate] {
+        &self.stack
+    }
+
     pub fn push(&mut self, krate: Crate) {
         self.stack.push(krate);
     }
@@ -223,6 +251,10 @@ impl Stack {
         self.stack.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
     pub fn lift(&mut self, start: usize) -> StackLift<'_> {
         let drain = self.stack.drain(start..);
         StackLift { drain }
@@ -232,6 +264,16 @@ impl Stack {
         let rev = self.lift(start).rev();
         StackLiftRev { rev }
     }
+
+    pub fn lift_bottom(&mut self, end: usize) -> StackLift<'_> {
+        let drain = self.stack.drain(..end);
+        StackLift { drain }
+    }
+
+    // Slides crates under the stack, keeping their order.
+    pub fn extend_bottom<I: IntoIterator<Item = Crate>>(&mut self, iter: I) {
+        self.stack.splice(..0, iter);
+    }
 }
 
 impl Extend<Crate> for Stack {
@@ -241,7 +283,7 @@ impl Extend<Crate> for Stack {
 }
 
 #[derive(Debug)]
-struct StackLift<'a> {
+pub struct StackLift<'a> {
     drain: vec::Drain<'a, Crate>,
 }
 
@@ -260,7 +302,7 @@ impl DoubleEndedIterator for StackLift<'_> {
 }
 
 #[derive(Debug)]
-struct StackLiftRev<'a> {
+pub struct StackLiftRev<'a> {
     rev: iter::Rev<StackLift<'a>>,
 }
 
@@ -283,17 +325,38 @@ pub struct Unchecked;
 #[derive(Debug, Copy, Clone)]
 pub struct Checked;
 
-#[derive(Copy, Clone)]
-pub struct Route<C> {
-    orig: usize,
-    dest: usize,
-    _pd: PhantomData<C>,
+pub trait RouteState {
+    type Id: Debug + Clone;
+}
+
+// Stacks are referred by the label written in the drawing.
+impl RouteState for Unchecked {
+    type Id = StackId;
+}
+
+// Stacks are referred by their position within the platform.
+impl RouteState for Checked {
+    type Id = usize;
+}
+
+pub struct Route<C: RouteState> {
+    orig: C::Id,
+    dest: C::Id,
 }
 
 type RouteUnchecked = Route<Unchecked>;
 type RouteChecked = Route<Checked>;
 
-impl<C: Debug> Debug for Route<C> {
+impl<C: RouteState> Clone for Route<C> {
+    fn clone(&self) -> Self {
+        let (orig, dest) = (self.orig.clone(), self.dest.clone());
+        Self { orig, dest }
+    }
+}
+
+impl Copy for RouteChecked {}
+
+impl<C: RouteState> Debug for Route<C> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         use std::any::type_name;
         f.debug_struct("Route")
@@ -304,30 +367,43 @@ impl<C: Debug> Debug for Route<C> {
     }
 }
 
-impl<C> Route<C> {
+impl<C: RouteState> Route<C> {
     const PREFIX: &str = "from";
     const DELIM: &str = "to";
 
     const PREFIX_ERR: RouteParseError = RouteParseError::Prefix(Self::PREFIX);
     const DELIM_ERR: RouteParseError = RouteParseError::Delim(Self::DELIM);
+}
 
+impl RouteUnchecked {
     #[inline]
     fn orig_err(&self) -> RouteError {
-        RouteError::Orig(self.orig)
+        RouteError::Orig(self.orig.clone())
     }
 
     #[inline]
     fn dest_err(&self) -> RouteError {
-        RouteError::Dest(self.dest)
+        RouteError::Dest(self.dest.clone())
+    }
+
+    fn check(&self, layout: &Layout) -> Result<RouteChecked, RouteError> {
+        let orig = layout.index(&self.orig).ok_or_else(|| self.orig_err())?;
+        let dest = layout.index(&self.dest).ok_or_else(|| self.dest_err())?;
+        if orig == dest {
+            return Err(RouteError::SelfRoute(self.orig.clone()));
+        }
+        Ok(Route { orig, dest })
     }
 }
 
+// Stack labels as they were written in the drawing.
 impl RouteUnchecked {
-    fn check(self, layout: &Layout) -> Result<RouteChecked, RouteError> {
-        let map = layout.map();
-        let orig = *map.get(&self.orig).ok_or_else(|| self.orig_err())?;
-        let dest = *map.get(&self.dest).ok_or_else(|| self.dest_err())?;
-        Ok(Route::<Checked> { orig, dest, _pd: PhantomData })
+    pub fn orig(&self) -> &StackId {
+        &self.orig
+    }
+
+    pub fn dest(&self) -> &StackId {
+        &self.dest
     }
 }
 
@@ -344,25 +420,31 @@ impl RouteChecked {
 impl FromStr for RouteUnchecked {
     type Err = RouteParseError;
 
+    // Labels may contain the delimiter, so the route is split in words.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.trim().strip_prefix(Self::PREFIX).ok_or(Self::PREFIX_ERR)?;
-        let (orig, dest) = s.split_once(Self::DELIM).ok_or(Self::DELIM_ERR)?;
-        let orig = orig.trim().parse().map_err(Self::Err::orig)?;
-        let dest = dest.trim().parse().map_err(Self::Err::dest)?;
-        Ok(Self { orig, dest, _pd: PhantomData })
+        let mut words = s.split_whitespace();
+        words.next().filter(|&word| word == Self::PREFIX).ok_or(Self::PREFIX_ERR)?;
+        let orig = words.next().unwrap_or_default().parse().map_err(Self::Err::orig)?;
+        words.next().filter(|&word| word == Self::DELIM).ok_or(Self::DELIM_ERR)?;
+        let dest = words.next().unwrap_or_default().parse().map_err(Self::Err::dest)?;
+        let None = words.next() else { return Err(Self::Err::TooMany) };
+        Ok(Self { orig, dest })
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Lift<C> {
+#[derive(Debug, Clone)]
+pub struct Lift<C: RouteState> {
     moves: usize,
     route: Route<C>,
+    pos: usize,
 }
 
 type LiftUnchecked = Lift<Unchecked>;
 type LiftChecked = Lift<Checked>;
 
-impl<C> Lift<C> {
+impl Copy for LiftChecked {}
+
+impl<C: RouteState> Lift<C> {
     const PREFIX: &str = "move ";
     const DELIM: char = ' ';
 
@@ -376,13 +458,33 @@ impl<C> Lift<C> {
     pub fn route(&self) -> &Route<C> {
         &self.route
     }
+
+    // Line of the drawing the lift was read from.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
 }
 
 impl LiftUnchecked {
-    fn check(self, layout: &Layout) -> Result<LiftChecked, RouteError> {
-        let route = self.route.check(layout)?;
-        let moves = self.moves;
-        Ok(Lift::<Checked> { route, moves })
+    fn check(&self, layout: &Layout) -> Result<LiftChecked, LiftError> {
+        let route = self.route.check(layout).map_err(|err| LiftError::new(self.pos, err))?;
+        let (moves, pos) = (self.moves, self.pos);
+        Ok(Lift::<Checked> { route, moves, pos })
+    }
+}
+
+impl Display for LiftUnchecked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Route { orig, dest } = &self.route;
+        write!(
+            f,
+            "{}{}{}{} {orig} {} {dest}",
+            Self::PREFIX,
+            self.moves,
+            Self::DELIM,
+            RouteUnchecked::PREFIX,
+            RouteUnchecked::DELIM
+        )
     }
 }
 
@@ -394,7 +496,7 @@ impl FromStr for LiftUnchecked {
         let (moves, route) = s.split_once(Self::DELIM).ok_or(Self::DELIM_ERR)?;
         let moves = moves.trim().parse().map_err(Self::Err::from)?;
         let route = route.trim().parse().map_err(Self::Err::from)?;
-        Ok(Self { moves, route })
+        Ok(Self { moves, route, pos: 0 })
     }
 }
 
@@ -406,7 +508,7 @@ impl<R: Read> LiftPeeker<R> {
         self.0
     }
 
-    fn lifts(self) -> (Lifts, LinePeeker<R>) {
+    fn lifts(self) -> Result<(Lifts, LinePeeker<R>), LiftReaderError> {
         Lifts::new(self)
     }
 }
@@ -417,16 +519,16 @@ impl<R: Read> From<LinePeeker<R>> for LiftPeeker<R> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Lifts {
     lifts: vec::IntoIter<LiftUnchecked>,
 }
 
 impl Lifts {
-    fn new<R: Read>(mut peeker: LiftPeeker<R>) -> (Self, LinePeeker<R>) {
-        let lifts = (&mut peeker).collect::<Vec<_>>().into_iter();
+    fn new<R: Read>(mut peeker: LiftPeeker<R>) -> Result<(Self, LinePeeker<R>), LiftReaderError> {
+        let lifts = (&mut peeker).collect::<Result<Vec<_>, _>>()?.into_iter();
         let peeker = peeker.into_inner();
-        (Self { lifts }, peeker)
+        Ok((Self { lifts }, peeker))
     }
 }
 
@@ -438,16 +540,23 @@ impl Iterator for Lifts {
     }
 }
 
+impl DoubleEndedIterator for Lifts {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.lifts.next_back()
+    }
+}
+
 impl<R: Read> ParseControlFlow for LiftPeeker<R> {
     type Item = LiftUnchecked;
     type ParseError = LiftParseError;
 }
 
-impl<R: Read> LinesParseIfOk for LiftPeeker<R> {
-    type InnerIter = Lines<BufReader<R>>;
-    type Peekable<'s> = &'s mut PeekableLines<R> where Self: 's;
+// Lifts are the last part of the drawing, so every line left must be one.
+impl<R: Read> LinesParse for LiftPeeker<R> {
+    type Error = LiftReaderErrorSource;
+    type Lines<'s> = &'s mut PeekableLines<R> where Self: 's;
 
-    fn peekable(&mut self) -> Self::Peekable<'_> {
+    fn lines(&mut self) -> Self::Lines<'_> {
         self.0.peekable()
     }
 
@@ -456,11 +565,20 @@ impl<R: Read> LinesParseIfOk for LiftPeeker<R> {
     }
 }
 
+impl<R: Read> LinesParseMap for LiftPeeker<R> {
+    type Result = Result<LiftUnchecked, LiftReaderError>;
+
+    fn map(&self, res: Result<Self::Item, Self::Error>) -> Self::Result {
+        let pos = self.0.pos();
+        res.map(|lift| Lift { pos, ..lift }).map_err(|err| LiftReaderError::new(pos, err))
+    }
+}
+
 impl<R: Read> Iterator for LiftPeeker<R> {
-    type Item = LiftUnchecked;
+    type Item = Result<LiftUnchecked, LiftReaderError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.parse_next_if_ok()
+        self.parse_next_map()
     }
 }
 
@@ -470,79 +588,214 @@ struct StackPairMut<'a> {
     pub dest: &'a mut Stack,
 }
 
-struct LiftParams<'a> {
-    pub pair: StackPairMut<'a>,
-    pub pos: usize,
+pub trait Crane {
+    // Moves `moves` crates from `orig` onto the top of `dest`, the platform
+    // makes sure that `orig` holds at least that many crates.
+    fn lift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize);
+
+    // Reverts a previous `lift` with the same arguments, `dest` holds at
+    // least `moves` crates.
+    fn unlift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize);
+}
+
+// Moves crates one at a time, so they land in reverse order.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        dest.extend(orig.lift_rev(orig.len() - moves));
+    }
+
+    fn unlift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        orig.extend(dest.lift_rev(dest.len() - moves));
+    }
+}
+
+// Moves all crates at once, so they keep their order.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        dest.extend(orig.lift(orig.len() - moves));
+    }
+
+    fn unlift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        orig.extend(dest.lift(dest.len() - moves));
+    }
+}
+
+// Moves at most `capacity` crates at once, bigger lifts are split in
+// several trips that each keep their crates' order.
+#[derive(Debug, Copy, Clone)]
+pub struct LimitedCrane {
+    capacity: NonZeroUsize,
+}
+
+impl LimitedCrane {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self { capacity }
+    }
+
+    // Crates moved on each trip, only the last one may be partial.
+    fn trips(&self, moves: usize) -> impl DoubleEndedIterator<Item = usize> {
+        let capacity = self.capacity.get();
+        (0..moves).step_by(capacity).map(move |moved| capacity.min(moves - moved))
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn lift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        for trip in self.trips(moves) {
+            dest.extend(orig.lift(orig.len() - trip));
+        }
+    }
+
+    fn unlift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        for trip in self.trips(moves).rev() {
+            orig.extend(dest.lift(dest.len() - trip));
+        }
+    }
+}
+
+// Pulls crates from the bottom of the origin stack, keeping their order.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BottomFirstCrane;
+
+impl Crane for BottomFirstCrane {
+    fn lift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        dest.extend(orig.lift_bottom(moves));
+    }
+
+    fn unlift(&self, orig: &mut Stack, dest: &mut Stack, moves: usize) {
+        orig.extend_bottom(dest.lift(dest.len() - moves));
+    }
 }
 
 #[derive(Debug)]
-pub struct Platform {
+pub struct Platform<C = CrateMover9001> {
     stacks: Vec<Stack>,
     layout: Layout,
+    crane: C,
 }
 
 impl Platform {
-    pub fn read<R: Read>(read: R) -> (Self, LinePeeker<R>) {
+    pub fn read<R: Read>(read: R) -> Result<(Self, LinePeeker<R>), CrateRowReaderError> {
         let peeker = LinePeeker::new(read);
-        let (rows, peeker) = CrateRowPeeker::from(peeker).rows();
-        let (layout, peeker) = LayoutPeeker::from(peeker).layout();
+        let (mut rows, peeker) = CrateRowPeeker::from(peeker).rows()?;
+        let (layout, peeker) = LayoutPeeker::from(peeker).layout()?;
         let mut platform = Platform::new(layout);
-        platform.extend(rows);
-        (platform, peeker)
+        rows.try_for_each(|row| platform.stack_row(row))?;
+        Ok((platform, peeker))
     }
 
     fn new(layout: Layout) -> Self {
         let stacks = vec![Default::default(); layout.len()];
-        Self { stacks, layout }
+        Self { stacks, layout, crane: CrateMover9001 }
+    }
+}
+
+impl<C: Crane> Platform<C> {
+    pub fn with_crane<K: Crane>(self, crane: K) -> Platform<K> {
+        let Self { stacks, layout, .. } = self;
+        Platform { stacks, layout, crane }
+    }
+
+    pub fn crane(&self) -> &C {
+        &self.crane
     }
 
-    fn stack_row(&mut self, row: CrateRow) {
-        for (pos, krate) in row.into_iter().enumerate() {
-            krate.map_or((), |krate| self.stacks[pos].push(krate));
+    fn stack_row(&mut self, row: CrateRow) -> Result<(), CrateRowReaderError> {
+        let pos = row.pos;
+        for (col, krate) in row {
+            let Some(idx) = self.layout.stack_at(col) else {
+                // Columns are counted from 1, just like lines.
+                let (krate, column) = (krate.value, col + 1);
+                let source = CrateRowReaderErrorSource::Unlabeled { krate, column };
+                return Err(CrateRowReaderError::new(pos, source));
+            };
+            self.stacks[idx].push(krate);
         }
+        Ok(())
     }
 
-    fn get_stacks_mut(&mut self, route: &RouteChecked) -> StackPairMut<'_> {
-        let [orig, dest] = self
-            .stacks
+    fn get_stacks_mut<'s>(stacks: &'s mut [Stack], route: &RouteChecked) -> StackPairMut<'s> {
+        // PANIC: checked routes have distinct origin and destination within the layout.
+        let [orig, dest] = stacks
             .get_many_mut([route.orig(), route.dest()])
-            .expect("out-of-bounds route origin or destination");
+            .expect("out-of-bounds or self route origin and destination");
         StackPairMut { orig, dest }
     }
 
-    fn lift_pos(&self, lift: &LiftChecked) -> usize {
-        self.stacks[lift.route().orig()].len() - lift.moves()
+    fn lift_crates(&self, lift: &LiftChecked) -> Result<(), LiftError> {
+        let (moves, crates) = (lift.moves(), self.stacks[lift.route().orig()].len());
+        let source = RouteError::Insufficient { moves, crates };
+        (moves <= crates).then_some(()).ok_or_else(|| LiftError::new(lift.pos(), source))
     }
 
-    fn lift_check(&self, lift: LiftUnchecked) -> Result<LiftChecked, RouteError> {
+    fn lift_check(&self, lift: &LiftUnchecked) -> Result<LiftChecked, LiftError> {
         lift.check(&self.layout)
     }
 
-    fn lift_params(&mut self, lift: LiftUnchecked) -> Result<LiftParams<'_>, RouteError> {
-        let lift = self.lift_check(lift)?;
-        let pos = self.lift_pos(&lift);
-        let pair = self.get_stacks_mut(lift.route());
-        Ok(LiftParams { pair, pos })
+    fn lift(&mut self, unchecked: &LiftUnchecked) -> Result<LiftChecked, LiftError> {
+        let lift = self.lift_check(unchecked)?;
+        self.lift_crates(&lift)?;
+        self.lift_checked(&lift);
+        Ok(lift)
     }
 
-    fn lift_rev(&mut self, lift: LiftUnchecked) -> Result<(), RouteError> {
-        let LiftParams { pair, pos } = self.lift_params(lift)?;
-        pair.dest.extend(pair.orig.lift_rev(pos));
-        Ok(())
+    fn lift_checked(&mut self, lift: &LiftChecked) {
+        let StackPairMut { orig, dest } = Self::get_stacks_mut(&mut self.stacks, lift.route());
+        self.crane.lift(orig, dest, lift.moves());
+    }
+
+    // Same as `lift` but also records the crates that were moved.
+    fn step(&mut self, unchecked: LiftUnchecked) -> Result<Step, LiftError> {
+        let lift = self.lift_check(&unchecked)?;
+        self.lift_crates(&lift)?;
+        Ok(self.step_checked(unchecked, &lift))
+    }
+
+    fn step_checked(&mut self, unchecked: LiftUnchecked, lift: &LiftChecked) -> Step {
+        let start = self.stacks[lift.route().dest()].len();
+        self.lift_checked(lift);
+        let crates = self.stacks[lift.route().dest()].stack[start..].to_vec();
+        Step { lift: unchecked, checked: *lift, crates }
     }
 
-    pub fn try_lifts_rev(&mut self, mut lifts: Lifts) -> Result<(), RouteError> {
-        lifts.try_for_each(|lift| self.lift_rev(lift))
+    pub fn try_lifts(&mut self, mut lifts: Lifts) -> Result<(), LiftError> {
+        lifts.try_for_each(|lift| self.lift(&lift).map(drop))
     }
 
-    fn lift(&mut self, lift: LiftUnchecked) -> Result<(), RouteError> {
-        let LiftParams { pair, pos } = self.lift_params(lift)?;
-        pair.dest.extend(pair.orig.lift(pos));
+    fn unlift(&mut self, unchecked: LiftUnchecked) -> Result<(), LiftError> {
+        let lift = self.lift_check(&unchecked)?;
+        let (moves, crates) = (lift.moves(), self.stacks[lift.route().dest()].len());
+        if moves > crates {
+            return Err(LiftError::new(lift.pos(), RouteError::Insufficient { moves, crates }));
+        }
+        self.unlift_checked(&lift);
         Ok(())
     }
 
-    pub fn try_lifts(&mut self, mut lifts: Lifts) -> Result<(), RouteError> {
-        lifts.try_for_each(|lift| self.lift(lift))
+    fn unlift_checked(&mut self, lift: &LiftChecked) {
+        let StackPairMut { orig, dest } = Self::get_stacks_mut(&mut self.stacks, lift.route());
+        self.crane.unlift(orig, dest, lift.moves());
+    }
+
+    // Runs lifts backwards from the last one, so the platform ends up as it
+    // was before they were all applied.
+    pub fn try_unlifts(&mut self, lifts: Lifts) -> Result<(), LiftError> {
+        lifts.rev().try_for_each(|lift| self.unlift(lift))
+    }
+
+    pub fn journaled(self) -> Journaled<C> {
+        Journaled { platform: self, done: Vec::new(), undone: Vec::new() }
+    }
+
+    // Applies one lift at a time, the platform can be inspected in between.
+    pub fn steps(&mut self, lifts: Lifts) -> Steps<'_, C> {
+        Steps { platform: self, lifts }
     }
 
     fn top_row(&self) -> impl Iterator<Item = Option<&'_ Crate>> {
@@ -556,24 +809,216 @@ impl Platform {
     }
 }
 
-impl Extend<CrateRow> for Platform {
-    fn extend<I: IntoIterator<Item = CrateRow>>(&mut self, iter: I) {
-        iter.into_iter().for_each(|row| self.stack_row(row));
+// The crates a lift moved, from bottom to top as they now sit on the
+// destination stack.
+#[derive(Debug, Clone)]
+pub struct Step {
+    lift: LiftUnchecked,
+    checked: LiftChecked,
+    crates: Vec<Crate>,
+}
+
+impl Step {
+    pub fn lift(&self) -> &Lift<Unchecked> {
+        &self.lift
     }
+
+    pub fn crates(&self) -> &[Crate] {
+        &self.crates
+    }
+}
+
+// Records every applied lift so that it can be undone and then redone,
+// applying a new lift discards the ones that could be redone.
+#[derive(Debug)]
+pub struct Journaled<C> {
+    platform: Platform<C>,
+    done: Vec<Step>,
+    undone: Vec<Step>,
 }
 
+impl<C: Crane> Journaled<C> {
+    pub fn lift(&mut self, lift: LiftUnchecked) -> Result<&Step, LiftError> {
+        let step = self.platform.step(lift)?;
+        self.undone.clear();
+        self.done.push(step);
+        Ok(&self.done[self.done.len() - 1])
+    }
+
+    pub fn try_lifts(&mut self, mut lifts: Lifts) -> Result<(), LiftError> {
+        lifts.try_for_each(|lift| self.lift(lift).map(drop))
+    }
+
+    pub fn undo(&mut self) -> Option<&Step> {
+        let step = self.done.pop()?;
+        self.platform.unlift_checked(&step.checked);
+        self.undone.push(step);
+        self.undone.last()
+    }
+
+    pub fn redo(&mut self) -> Option<&Step> {
+        let step = self.undone.pop()?;
+        let step = self.platform.step_checked(step.lift, &step.checked);
+        self.done.push(step);
+        self.done.last()
+    }
+
+    // Applied lifts, from the first to the last one.
+    pub fn journal(&self) -> &[Step] {
+        &self.done
+    }
+
+    pub fn platform(&self) -> &Platform<C> {
+        &self.platform
+    }
+
+    pub fn into_inner(self) -> Platform<C> {
+        self.platform
+    }
+}
+
+// The platform can be inspected in between steps for a snapshot of it.
+pub struct Steps<'p, C> {
+    platform: &'p mut Platform<C>,
+    lifts: Lifts,
+}
+
+impl<C> Steps<'_, C> {
+    pub fn platform(&self) -> &Platform<C> {
+        self.platform
+    }
+}
+
+impl<C: Crane> Iterator for Steps<'_, C> {
+    type Item = Result<Step, LiftError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lift = self.lifts.next()?;
+        Some(self.platform.step(lift))
+    }
+}
+
+// Draws the stacks just like the puzzle's input, so the output can be read back.
+impl<C> Display for Platform<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (height, width) = (self.stacks.iter().map(Stack::len).max(), self.layout.width());
+        let offset = self.layout.offset();
+        for level in (0..height.unwrap_or_default()).rev() {
+            let mut at = 0;
+            for (stack, label) in self.stacks.iter().zip(&self.layout.labels) {
+                let Some(krate) = stack.stack.get(level) else { continue };
+                let start = label.crate_col() + offset - 1;
+                write!(f, "{:1$}{krate}", "", start.saturating_sub(at))?;
+                at = at.max(start) + Crate::LEN;
+            }
+            writeln!(f, "{:1$}", "", width.saturating_sub(at))?;
+        }
+        write!(f, "{}", self.layout)
+    }
+}
+
+// Labels stacks in the drawing, any run of alphanumeric chars.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StackId(String);
+
+impl StackId {
+    // Columns the label takes in the drawing.
+    fn len(&self) -> usize {
+        self.0.chars().count()
+    }
+}
+
+impl Display for StackId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(&self.0)
+    }
+}
+
+impl FromStr for StackId {
+    type Err = StackIdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars().peekable();
+        chars.peek().ok_or(Self::Err::Empty)?;
+        match chars.find(|c| !c.is_alphanumeric()) {
+            Some(c) => Err(Self::Err::Char(c)),
+            None => Ok(Self(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Label {
+    id: StackId,
+    col: usize,
+}
+
+impl Label {
+    fn end(&self) -> usize {
+        self.col + self.id.len()
+    }
+
+    // Crates are drawn with their identifier over the label.
+    fn crate_col(&self) -> usize {
+        self.col + (self.id.len() - 1) / 2
+    }
+}
+
+// Stack labels from left to right, each one at the column it was drawn.
 #[derive(Debug, Default)]
 struct Layout {
-    layout: HashMap<usize, usize>,
+    labels: Vec<Label>,
+    layout: HashMap<StackId, usize>,
 }
 
 impl Layout {
     pub fn len(&self) -> usize {
-        self.layout.len()
+        self.labels.len()
+    }
+
+    fn index(&self, id: &StackId) -> Option<usize> {
+        self.layout.get(id).copied()
     }
 
-    fn map(&self) -> &HashMap<usize, usize> {
-        &self.layout
+    // Stack whose label spans over the column.
+    fn stack_at(&self, col: usize) -> Option<usize> {
+        let idx = self.labels.partition_point(|label| label.col <= col).checked_sub(1)?;
+        (col < self.labels[idx].end()).then_some(idx)
+    }
+
+    // Columns the whole drawing is shifted by, so that the left delimiter of
+    // crates over a label at the first column still fits in the line.
+    fn offset(&self) -> usize {
+        self.labels.iter().any(|label| label.crate_col() == 0).into()
+    }
+
+    // Columns needed to draw every label along with the crates over them.
+    fn width(&self) -> usize {
+        let ends = self.labels.iter().map(|label| label.end().max(label.crate_col() + 2));
+        ends.max().map_or(0, |end| end + self.offset())
+    }
+
+    fn push(&mut self, id: StackId, col: usize) -> Result<(), StacksLayoutParseError> {
+        match self.layout.entry(id) {
+            Entry::Vacant(entry) => {
+                let id = entry.key().clone();
+                entry.insert(self.labels.len());
+                self.labels.push(Label { id, col });
+                Ok(())
+            }
+            Entry::Occupied(entry) => Err(StacksLayoutParseError::Duplicate(entry.key().clone())),
+        }
+    }
+}
+
+impl Display for Layout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (offset, mut at) = (self.offset(), 0);
+        for label in &self.labels {
+            write!(f, "{:1$}{2}", "", label.col + offset - at, label.id)?;
+            at = label.end() + offset;
+        }
+        write!(f, "{:1$}", "", self.width() - at)
     }
 }
 
@@ -581,16 +1026,24 @@ impl FromStr for Layout {
     type Err = StacksLayoutParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut layout = HashMap::new();
-        for (idx, id) in s.split_whitespace().into_iter().enumerate() {
-            let id = id.parse()?;
-            if let Entry::Vacant(entry) = layout.entry(id) {
-                entry.insert(idx);
-            } else {
-                return Err(Self::Err::Duplicate(id));
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(Self::Err::Missing);
+        }
+        if trimmed.starts_with(LiftUnchecked::PREFIX) {
+            return Err(Self::Err::Lift);
+        }
+        let mut layout = Self::default();
+        let mut chars = s.chars().enumerate().peekable();
+        while let Some((col, c)) = chars.next() {
+            if c.is_whitespace() {
+                continue;
             }
+            let rest = iter::from_fn(|| chars.next_if(|(_, c)| !c.is_whitespace()));
+            let id = iter::once(c).chain(rest.map(|(_, c)| c)).collect::<String>();
+            layout.push(id.parse()?, col)?;
         }
-        Ok(Self { layout })
+        Ok(layout)
     }
 }
 
@@ -602,10 +1055,11 @@ impl<R: Read> LayoutPeeker<R> {
         self.0
     }
 
-    fn layout(mut self) -> (Layout, LinePeeker<R>) {
-        let layout = self.next().unwrap_or_default();
+    // A drawing without a label row has no stacks.
+    fn layout(mut self) -> Result<(Layout, LinePeeker<R>), CrateRowReaderError> {
+        let layout = self.next().transpose()?.unwrap_or_default();
         let peeker = self.into_inner();
-        (layout, peeker)
+        Ok((layout, peeker))
     }
 }
 
@@ -618,13 +1072,20 @@ impl<R: Read> From<LinePeeker<R>> for LayoutPeeker<R> {
 impl<R: Read> ParseControlFlow for LayoutPeeker<R> {
     type Item = Layout;
     type ParseError = StacksLayoutParseError;
+
+    // The label row comes right after the crates, so blank lines are not
+    // skipped. Just like crate rows, labels are placed by their column.
+    fn parse(s: &str) -> ControlFlow<Result<Self::Item, Self::ParseError>, ()> {
+        Break(s.parse())
+    }
 }
 
-impl<R: Read> LinesParseIfOk for LayoutPeeker<R> {
-    type InnerIter = Lines<BufReader<R>>;
-    type Peekable<'s> = &'s mut PeekableLines<R> where Self: 's;
+// The label row must follow the crates, so it is reported if it's malformed.
+impl<R: Read> LinesParse for LayoutPeeker<R> {
+    type Error = CrateRowReaderErrorSource;
+    type Lines<'s> = &'s mut PeekableLines<R> where Self: 's;
 
-    fn peekable(&mut self) -> Self::Peekable<'_> {
+    fn lines(&mut self) -> Self::Lines<'_> {
         self.0.peekable()
     }
 
@@ -633,11 +1094,19 @@ impl<R: Read> LinesParseIfOk for LayoutPeeker<R> {
     }
 }
 
+impl<R: Read> LinesParseMap for LayoutPeeker<R> {
+    type Result = Result<Layout, CrateRowReaderError>;
+
+    fn map(&self, res: Result<Self::Item, Self::Error>) -> Self::Result {
+        res.map_err(|err| CrateRowReaderError::new(self.0.pos(), err))
+    }
+}
+
 impl<R: Read> Iterator for LayoutPeeker<R> {
-    type Item = Layout;
+    type Item = Result<Layout, CrateRowReaderError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.parse_next_if_ok()
+        self.parse_next_map()
     }
 }
 
@@ -650,7 +1119,6 @@ pub struct CrateRowReaderError {
 }
 
 impl CrateRowReaderError {
-    #[allow(dead_code)]
     fn new(pos: usize, source: CrateRowReaderErrorSource) -> Self {
         Self { pos, source }
     }
@@ -662,6 +1130,18 @@ pub enum CrateRowReaderErrorSource {
     Io(#[from] io::Error),
     #[error(transparent)]
     Parse(#[from] CrateParseError),
+    #[error(transparent)]
+    Layout(#[from] StacksLayoutParseError),
+    #[error("crate: `{krate}` at column: {column} sits over no stack label")]
+    Unlabeled { krate: char, column: usize },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DrawingError {
+    #[error(transparent)]
+    Crates(#[from] CrateRowReaderError),
+    #[error(transparent)]
+    Lifts(#[from] LiftReaderError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -673,7 +1153,6 @@ pub struct LiftReaderError {
 }
 
 impl LiftReaderError {
-    #[allow(dead_code)]
     fn new(pos: usize, source: LiftReaderErrorSource) -> Self {
         Self { pos, source }
     }
@@ -709,25 +1188,35 @@ pub enum RouteParseError {
     Orig(#[from] OrigParseError),
     #[error(transparent)]
     Dest(#[from] DestParseError),
+    #[error("unexpected words after the route destination")]
+    TooMany,
 }
 
 impl RouteParseError {
-    fn orig(err: ParseIntError) -> Self {
+    fn orig(err: StackIdParseError) -> Self {
         Self::Orig(OrigParseError(err))
     }
 
-    fn dest(err: ParseIntError) -> Self {
+    fn dest(err: StackIdParseError) -> Self {
         Self::Dest(DestParseError(err))
     }
 }
 
 #[derive(Debug, thiserror::Error)]
 #[error("could not parse route origin: {0}")]
-pub struct OrigParseError(#[from] ParseIntError);
+pub struct OrigParseError(#[from] StackIdParseError);
 
 #[derive(Debug, thiserror::Error)]
 #[error("could not parse route destination: {0}")]
-pub struct DestParseError(#[from] ParseIntError);
+pub struct DestParseError(#[from] StackIdParseError);
+
+#[derive(Debug, Copy, Clone, thiserror::Error)]
+pub enum StackIdParseError {
+    #[error("missing stack label")]
+    Empty,
+    #[error("stack labels must be alphanumeric, found: `{0}`")]
+    Char(char),
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum LiftParseError {
@@ -741,20 +1230,50 @@ pub enum LiftParseError {
     Qnt(#[from] ParseIntError),
 }
 
-#[derive(Debug, Copy, Clone, thiserror::Error)]
+#[derive(Debug, thiserror::Error)]
+pub enum AnimationError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Lift(#[from] LiftError),
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("error at line: {pos}, {source}")]
+pub struct LiftError {
+    pos: usize,
+    #[source]
+    source: RouteError,
+}
+
+impl LiftError {
+    fn new(pos: usize, source: RouteError) -> Self {
+        Self { pos, source }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum RouteError {
     #[error("invalid stack origin: {0}")]
-    Orig(usize),
+    Orig(StackId),
     #[error("invalid stack destination: {0}")]
-    Dest(usize),
+    Dest(StackId),
+    #[error("stack: {0} is both the origin and destination")]
+    SelfRoute(StackId),
+    #[error("can't move {moves} crates from a stack with {crates}")]
+    Insufficient { moves: usize, crates: usize },
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum StacksLayoutParseError {
-    #[error("duplicate stack position: {0}")]
-    Duplicate(usize),
+    #[error("duplicate stack label: {0}")]
+    Duplicate(StackId),
+    #[error("missing stack labels after the crates")]
+    Missing,
+    #[error("expected stack labels after the crates, found a lift")]
+    Lift,
     #[error(transparent)]
-    Parse(#[from] ParseIntError),
+    Id(#[from] StackIdParseError),
 }
 
 #[cfg(test)]
@@ -786,19 +1305,279 @@ move 7 from 8 to 1
 move 9 from 3 to 9
 move 11 from 6 to 5";
 
+    fn label(id: &str) -> StackId {
+        id.parse().unwrap()
+    }
+
     fn test_drawing() -> (Platform, Lifts) {
         let input = Cursor::new(SAMPLE);
-        drawing(input)
+        drawing(input).unwrap()
     }
 
     #[test]
     fn solve_sample_lift1() {
-        let (mut platform, lifts) = test_drawing();
-        platform.try_lifts_rev(lifts).unwrap();
+        let (platform, lifts) = test_drawing();
+        let mut platform = platform.with_crane(CrateMover9000);
+        platform.try_lifts(lifts).unwrap();
         let answer = platform.collect_top_row::<String>();
         assert_eq!("MFHDVFL M", answer);
     }
 
+    #[test]
+    fn invalid_lifts() {
+        const INVALID: &str = "[A]    
+[B] [C]
+ 1   2 
+
+move 3 from 1 to 2
+move 1 from 2 to 2
+
+move 1 from 3 to 1
+move 2 from 1 to 2";
+        let (mut platform, mut lifts) = drawing(Cursor::new(INVALID)).unwrap();
+        let mut next_err = || {
+            let LiftError { pos, source } = platform.lift(&lifts.next().unwrap()).unwrap_err();
+            (pos, source)
+        };
+        assert!(matches!(next_err(), (5, RouteError::Insufficient { moves: 3, crates: 2 })));
+        assert_eq!((6, RouteError::SelfRoute(label("2"))), next_err());
+        assert_eq!((8, RouteError::Orig(label("3"))), next_err());
+        platform.try_lifts(lifts).unwrap();
+        assert_eq!(" A", platform.collect_top_row::<String>());
+    }
+
+    #[test]
+    fn malformed_lifts() {
+        const MALFORMED: &str = "[A]    
+[B] [C]
+ 1   2 
+
+move 1 from 1 to 2
+mvoe 1 from 2 to 1
+
+move 1 from 2 to 1";
+        let Err(DrawingError::Lifts(err)) = drawing(Cursor::new(MALFORMED)) else {
+            panic!("expected a lift error")
+        };
+        let LiftReaderError { pos, source } = err;
+        assert!(matches!(
+            (pos, source),
+            (6, LiftReaderErrorSource::Parse(LiftParseError::Prefix("move ")))
+        ));
+        let (_, lifts) = drawing(Cursor::new(MALFORMED.replace("mvoe", "move"))).unwrap();
+        assert_eq!(vec![5, 6, 8], lifts.map(|lift| lift.pos()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn labeled_layouts() {
+        const LABELED: &str = "        [C]
+[A]     [B]  [D]
+ x       y2  zz
+
+move 1 from y2 to x
+move 1 from zz to x
+move 1 from y2 to q";
+        const LIFTED: &str = "[D]            
+[C]            
+[A]     [B]    
+ x       y2  zz";
+        let (mut platform, mut lifts) = drawing(Cursor::new(LABELED)).unwrap();
+        let lift = lifts.next_back().unwrap();
+        assert_eq!(
+            (label("y2"), label("q")),
+            (lift.route().orig().clone(), lift.route().dest().clone())
+        );
+        assert_eq!(RouteError::Dest(label("q")), platform.lift(&lift).unwrap_err().source);
+        platform.try_lifts(lifts).unwrap();
+        assert_eq!("DB ", platform.collect_top_row::<String>());
+        assert_eq!(LIFTED, platform.to_string());
+
+        let err = "move 1 from y2 to x y2".parse::<LiftUnchecked>().unwrap_err();
+        assert!(matches!(err, LiftParseError::Route(RouteParseError::TooMany)));
+        let err = "from x- to y2".parse::<RouteUnchecked>().unwrap_err();
+        assert!(matches!(err, RouteParseError::Orig(OrigParseError(StackIdParseError::Char('-')))));
+
+        // A first label at column 0 shifts the whole drawing to the right.
+        let shifted = "    [A]\n1    2\n\nmove 1 from 2 to 1";
+        let (mut platform, lifts) = drawing(Cursor::new(shifted)).unwrap();
+        let round_trip = |rendered: &str| {
+            let (read, _) = Platform::read(Cursor::new(rendered)).unwrap();
+            assert_eq!(rendered, read.to_string());
+        };
+        assert_eq!("     [A]\n 1    2 ", platform.to_string());
+        round_trip(&platform.to_string());
+        platform.try_lifts(lifts).unwrap();
+        assert_eq!("[A]     \n 1    2 ", platform.to_string());
+        round_trip(&platform.to_string());
+
+        let unlabeled = "[A]     [B]\n 1   2";
+        let CrateRowReaderError { pos, source } =
+            Platform::read(Cursor::new(unlabeled)).unwrap_err();
+        assert_eq!(1, pos);
+        assert!(matches!(source, CrateRowReaderErrorSource::Unlabeled { krate: 'B', column: 10 }));
+        let duplicate = " 1   1".parse::<Layout>().unwrap_err();
+        assert!(matches!(duplicate, StacksLayoutParseError::Duplicate(id) if id == label("1")));
+        let layout_err = |input: &str| {
+            let Err(DrawingError::Crates(err)) = drawing(Cursor::new(input)) else {
+                panic!("expected a crates error")
+            };
+            let CrateRowReaderError { pos, source } = err;
+            let CrateRowReaderErrorSource::Layout(err) = source else {
+                panic!("expected a layout error")
+            };
+            (pos, err)
+        };
+        let (pos, err) = layout_err("[A] [B]\n 1   1 \n\nmove 1 from 1 to 1");
+        assert_eq!(2, pos);
+        assert!(matches!(err, StacksLayoutParseError::Duplicate(id) if id == label("1")));
+        let (pos, err) = layout_err(" 1   1 \n\nmove 1 from 1 to 1");
+        assert!(matches!((pos, err), (1, StacksLayoutParseError::Duplicate(_))));
+        let (pos, err) = layout_err("[A]\n 1- \n\nmove 1 from 1 to 1");
+        assert!(matches!((pos, err), (2, StacksLayoutParseError::Id(_))));
+        // The label row must come right after the crates.
+        let (pos, err) = layout_err("[A]\n\nmove 1 from 2 to 3\nmove 1 from move to 1");
+        assert!(matches!((pos, err), (2, StacksLayoutParseError::Missing)));
+        let (pos, err) = layout_err("[A] [B]\n\nmove 1 from 1 to 2");
+        assert!(matches!((pos, err), (2, StacksLayoutParseError::Missing)));
+        let (pos, err) = layout_err("[A]\nmove 1 from 1 to 2");
+        assert!(matches!((pos, err), (2, StacksLayoutParseError::Lift)));
+
+        let crate_err = |input: &str| {
+            let Err(DrawingError::Crates(err)) = drawing(Cursor::new(input)) else {
+                panic!("expected a crates error")
+            };
+            let CrateRowReaderError { pos, source } = err;
+            let CrateRowReaderErrorSource::Parse(err) = source else {
+                panic!("expected a crate parse error")
+            };
+            (pos, err)
+        };
+        let (pos, err) = crate_err("[A] [B\n 1   2 \n\nmove 1 from 1 to 2");
+        assert!(matches!((pos, err), (1, CrateParseError::Suffix(']'))));
+        let (pos, err) = crate_err("    [C]\n[A] [BC]\n 1   2 \n\nmove 1 from 1 to 2");
+        assert!(matches!((pos, err), (2, CrateParseError::Suffix(']'))));
+    }
+
+    #[test]
+    fn render_platform() {
+        const LIFTED: &str = "[M]                                
+[J]                                
+[B]                                
+[S]                                
+[V]                                
+[D]             [V]                
+[N] [F]         [Q]                
+[D] [J]         [D]             [M]
+[P] [S]         [G]             [F]
+[F] [J]         [T]             [D]
+[G] [H]         [Z]             [G]
+[V] [V]         [Q]             [P]
+[R] [B]         [G]     [L]     [C]
+[J] [M]         [N]     [H]     [L]
+[L] [P] [H] [D] [L] [F] [N]     [B]
+[D] [T] [V] [M] [G] [N] [F]     [D]
+ 1   2   3   4   5   6   7   8   9 ";
+        let (platform, lifts) = test_drawing();
+        let drawing = SAMPLE.lines().take(9).collect::<Vec<_>>().join("\n");
+        assert_eq!(drawing, platform.to_string());
+        let mut platform = platform.with_crane(CrateMover9000);
+        platform.try_lifts(lifts).unwrap();
+        assert_eq!(LIFTED, platform.to_string());
+        let (read, _) = Platform::read(Cursor::new(platform.to_string())).unwrap();
+        assert_eq!(LIFTED, read.to_string());
+        assert_eq!("MFHDVFL M", read.collect_top_row::<String>());
+    }
+
+    #[test]
+    fn step_through_lifts() {
+        let (platform, lifts) = test_drawing();
+        let mut platform = platform.with_crane(CrateMover9000);
+        let mut steps = platform.steps(lifts);
+        let step = steps.next().unwrap().unwrap();
+        assert_eq!(
+            (11, "move 3 from 4 to 6"),
+            (step.lift().pos(), step.lift().to_string().as_str())
+        );
+        assert_eq!("QGN", step.crates().iter().map(char::from).collect::<String>());
+        assert_eq!("FSCPFNLND", steps.platform().collect_top_row::<String>());
+        assert_eq!(12, steps.count());
+        assert_eq!("MFHDVFL M", platform.collect_top_row::<String>());
+
+        let (mut platform, lifts) = test_drawing();
+        let mut frames = Vec::new();
+        animate(platform.steps(lifts), &mut frames, Duration::ZERO).unwrap();
+        let frames = String::from_utf8(frames).unwrap();
+        assert_eq!(14, frames.matches("\x1b[2J").count());
+        assert!(frames.ends_with(&format!("{platform}\n\nline 23: move 11 from 6 to 5\n")));
+    }
+
+    #[test]
+    fn crane_models() {
+        const STACKS: &str = "[E]    
+[D]    
+[C]    
+[B]    
+[A] [F]
+ 1   2 
+
+move 4 from 1 to 2";
+        fn lifted<C: Crane>(crane: C) -> String {
+            let (platform, lifts) = drawing(Cursor::new(STACKS)).unwrap();
+            let mut platform = platform.with_crane(crane);
+            platform.try_lifts(lifts).unwrap();
+            let stack = |stack: &Stack| stack.crates().iter().map(char::from).collect::<String>();
+            platform.stacks.iter().map(stack).collect::<Vec<_>>().join("|")
+        }
+        assert_eq!("A|FEDCB", lifted(CrateMover9000));
+        assert_eq!("A|FBCDE", lifted(CrateMover9001));
+        assert_eq!("A|FCDEB", lifted(LimitedCrane::new(NonZeroUsize::new(3).unwrap())));
+        assert_eq!("E|FABCD", lifted(BottomFirstCrane));
+    }
+
+    #[test]
+    fn reverse_lifts() {
+        fn unlifted<C: Crane>(crane: C) -> String {
+            let (platform, lifts) = test_drawing();
+            let mut platform = platform.with_crane(crane);
+            platform.try_lifts(lifts.clone()).unwrap();
+            platform.try_unlifts(lifts).unwrap();
+            platform.to_string()
+        }
+        let drawing = SAMPLE.lines().take(9).collect::<Vec<_>>().join("\n");
+        assert_eq!(drawing, unlifted(CrateMover9000));
+        assert_eq!(drawing, unlifted(CrateMover9001));
+        assert_eq!(drawing, unlifted(LimitedCrane::new(NonZeroUsize::new(2).unwrap())));
+        assert_eq!(drawing, unlifted(BottomFirstCrane));
+
+        let (platform, lifts) = test_drawing();
+        let mut platform = platform.with_crane(CrateMover9000);
+        let err = platform.try_unlifts(lifts).unwrap_err();
+        assert!(matches!(err.source, RouteError::Insufficient { moves: 11, crates: 5 }));
+    }
+
+    #[test]
+    fn undo_redo_lifts() {
+        let (platform, mut lifts) = test_drawing();
+        let drawing = platform.to_string();
+        let mut journaled = platform.journaled();
+        journaled.try_lifts(lifts.clone()).unwrap();
+        assert_eq!(13, journaled.journal().len());
+        let lifted = journaled.platform().to_string();
+        let step = journaled.undo().unwrap();
+        assert_eq!((23, 11), (step.lift().pos(), step.crates().len()));
+        while journaled.undo().is_some() {}
+        assert_eq!(drawing, journaled.platform().to_string());
+        assert_eq!(11, journaled.redo().unwrap().lift().pos());
+        while journaled.redo().is_some() {}
+        assert_eq!(lifted, journaled.platform().to_string());
+
+        journaled.undo().unwrap();
+        let lift = lifts.next_back().unwrap();
+        assert_eq!(23, journaled.lift(lift).unwrap().lift().pos());
+        assert!(journaled.redo().is_none());
+        assert_eq!("NNHDGFH L", journaled.into_inner().collect_top_row::<String>());
+    }
+
     #[test]
     fn solve_sample_lift2() {
         let (mut platform, lifts) = test_drawing();
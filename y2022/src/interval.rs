@@ -0,0 +1,370 @@
+use std::ops::RangeInclusive;
+
+// Discrete points that an interval can be built from.
+pub trait Point: Copy + Ord {
+    fn succ(self) -> Option<Self>;
+
+    fn pred(self) -> Option<Self>;
+
+    // Number of points from `start` to `end`, both included.
+    fn span(start: Self, end: Self) -> u128;
+}
+
+macro_rules! impl_point {
+    ($($t:ty),*) => {
+        $(impl Point for $t {
+            fn succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+
+            fn span(start: Self, end: Self) -> u128 {
+                (end as i128 - start as i128) as u128 + 1
+            }
+        })*
+    };
+}
+
+impl_point!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+// Closed interval, just like `RangeInclusive` it is empty if `start > end`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Point> Interval<T> {
+    pub fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    pub fn len(&self) -> u128 {
+        match self.is_empty() {
+            true => 0,
+            false => T::span(self.start, self.end),
+        }
+    }
+
+    pub fn contains(&self, point: T) -> bool {
+        self.start <= point && point <= self.end
+    }
+
+    // Every point of `other` is also in `self`, empty intervals are covered by anything.
+    pub fn covers(&self, other: &Self) -> bool {
+        other.is_empty() || (self.start <= other.start && self.end >= other.end)
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    // Disjoint intervals with no point in between them.
+    pub fn is_adjacent(&self, other: &Self) -> bool {
+        let touches = |a: &Self, b: &Self| a.end.succ() == Some(b.start);
+        !self.is_empty() && !other.is_empty() && (touches(self, other) || touches(other, self))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    pub fn union(&self, other: &Self) -> IntervalSet<T> {
+        IntervalSet::from_iter([*self, *other])
+    }
+
+    pub fn difference(&self, other: &Self) -> IntervalSet<T> {
+        let mut set = IntervalSet::from_iter([*self]);
+        set.remove(*other);
+        set
+    }
+
+    // Smallest interval with the points of both, only non-empty ones are considered.
+    fn hull(&self, other: &Self) -> Self {
+        Self::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    // There is at least one point between the end of `self` and the start of `other`.
+    fn before(&self, other: &Self) -> bool {
+        self.end.succ().map_or(false, |next| next < other.start)
+    }
+}
+
+impl<T: Point> From<RangeInclusive<T>> for Interval<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (start, end) = range.into_inner();
+        Self::new(start, end)
+    }
+}
+
+impl<T: Point> From<Interval<T>> for RangeInclusive<T> {
+    fn from(interval: Interval<T>) -> Self {
+        RangeInclusive::new(interval.start, interval.end)
+    }
+}
+
+// Sorted intervals where overlapping and adjacent ones are merged together.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: Point> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { intervals: Vec::new() }
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let start = self.intervals.partition_point(|i| i.before(&interval));
+        let merged = self.intervals[start..].iter().take_while(|i| !interval.before(i));
+        let (end, hull) = merged.fold((start, interval), |(end, hull), i| (end + 1, hull.hull(i)));
+        self.intervals.splice(start..end, [hull]);
+    }
+
+    pub fn remove(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let start = self.intervals.partition_point(|i| i.end < interval.start);
+        let len = self.intervals[start..].iter().take_while(|i| i.start <= interval.end).count();
+        let pieces = self.intervals[start..start + len].iter().flat_map(|i| {
+            let before = interval.start.pred().map(|end| Interval::new(i.start, end));
+            let after = interval.end.succ().map(|start| Interval::new(start, i.end));
+            [before, after].into_iter().flatten().filter(|piece| !piece.is_empty())
+        });
+        let pieces = pieces.collect::<Vec<_>>();
+        self.intervals.splice(start..start + len, pieces);
+    }
+
+    pub fn contains(&self, point: T) -> bool {
+        let idx = self.intervals.partition_point(|i| i.end < point);
+        self.intervals.get(idx).map_or(false, |i| i.contains(point))
+    }
+
+    // Number of points covered by the set.
+    pub fn len(&self) -> u128 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.intervals.iter()
+    }
+}
+
+impl<T: Point> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Point> Extend<Interval<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Interval<T>>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|interval| self.insert(interval));
+    }
+}
+
+impl<T: Point> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+// Static augmented interval tree, entries are kept sorted by their start
+// and the implicit tree over them stores the greatest end of each subtree.
+#[derive(Debug, Clone)]
+pub struct IntervalTree<T, V> {
+    entries: Vec<(Interval<T>, V)>,
+    max_end: Vec<T>,
+}
+
+impl<T: Point, V> IntervalTree<T, V> {
+    pub fn new(mut entries: Vec<(Interval<T>, V)>) -> Self {
+        entries.retain(|(interval, _)| !interval.is_empty());
+        entries.sort_by_key(|(interval, _)| (interval.start, interval.end));
+        let mut max_end = entries.iter().map(|(interval, _)| interval.end).collect::<Vec<_>>();
+        Self::augment(&mut max_end, 0, entries.len());
+        Self { entries, max_end }
+    }
+
+    fn augment(max_end: &mut [T], lo: usize, hi: usize) -> Option<T> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::augment(max_end, lo, mid);
+        let right = Self::augment(max_end, mid + 1, hi);
+        let end = [left, right].into_iter().flatten().fold(max_end[mid], T::max);
+        max_end[mid] = end;
+        Some(end)
+    }
+
+    // Entries that share at least one point with `interval`, sorted by their start.
+    pub fn overlapping(&self, interval: Interval<T>) -> Vec<&(Interval<T>, V)> {
+        let mut found = Vec::new();
+        if !interval.is_empty() {
+            self.search(&interval, 0, self.entries.len(), &mut found);
+        }
+        found
+    }
+
+    fn search<'t>(
+        &'t self,
+        interval: &Interval<T>,
+        lo: usize,
+        hi: usize,
+        found: &mut Vec<&'t (Interval<T>, V)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] < interval.start {
+            return;
+        }
+        self.search(interval, lo, mid, found);
+        let entry = &self.entries[mid];
+        if entry.0.start > interval.end {
+            return;
+        }
+        if entry.0.overlaps(interval) {
+            found.push(entry);
+        }
+        self.search(interval, mid + 1, hi, found);
+    }
+
+    pub fn stabbing(&self, point: T) -> Vec<&(Interval<T>, V)> {
+        self.overlapping(Interval::new(point, point))
+    }
+
+    // Lowest point covered by the most entries, found by sweeping over the
+    // bounds. Starts are handled before ends at the same point since both
+    // bounds are included.
+    pub fn max_overlap(&self) -> Option<(T, usize)> {
+        let starts = self.entries.iter().map(|(interval, _)| (interval.start, false));
+        let ends = self.entries.iter().map(|(interval, _)| (interval.end, true));
+        let mut events = starts.chain(ends).collect::<Vec<_>>();
+        events.sort_unstable();
+        let (mut depth, mut max) = (0, None);
+        for (point, end) in events {
+            match end {
+                true => depth -= 1,
+                false => depth += 1,
+            }
+            if max.map_or(true, |(_, count)| depth > count) {
+                max = Some((point, depth));
+            }
+        }
+        max
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Interval<T>, V)> {
+        self.entries.iter()
+    }
+}
+
+impl<T: Point, V> FromIterator<(Interval<T>, V)> for IntervalTree<T, V> {
+    fn from_iter<I: IntoIterator<Item = (Interval<T>, V)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(intervals: &[(i32, i32)]) -> IntervalSet<i32> {
+        intervals.iter().map(|&(start, end)| Interval::new(start, end)).collect()
+    }
+
+    #[test]
+    fn interval_operations() {
+        let (a, b) = (Interval::new(2, 6), Interval::new(4, 8));
+        assert_eq!((5, 5), (a.len(), b.len()));
+        assert_eq!(Interval::new(4, 6), a.intersection(&b));
+        assert!(a.overlaps(&b) && !a.covers(&b));
+        assert!(Interval::new(1, 9).covers(&a));
+        assert_eq!(set(&[(2, 8)]), a.union(&b));
+        assert_eq!(set(&[(2, 3)]), a.difference(&b));
+        assert_eq!(set(&[(2, 3), (7, 8)]), Interval::new(2, 8).difference(&Interval::new(4, 6)));
+        assert!(Interval::new(7, 9).is_adjacent(&a) && a.is_adjacent(&Interval::new(0, 1)));
+        assert!(!a.is_adjacent(&b) && !Interval::new(8, 9).is_adjacent(&a));
+        assert!(Interval::new(6, 2).is_empty() && !a.overlaps(&Interval::new(9, 1)));
+        assert_eq!(0, Interval::new(6, 2).len());
+        assert_eq!(1 << 64, Interval::new(0, u64::MAX).len());
+        assert_eq!(set(&[]), Interval::new(i32::MIN, 1).difference(&Interval::new(i32::MIN, 4)));
+    }
+
+    #[test]
+    fn interval_set_merges() {
+        let mut merged = set(&[(10, 12), (1, 3), (5, 6), (4, 4), (20, 19)]);
+        assert_eq!(&[Interval::new(1, 6), Interval::new(10, 12)], merged.intervals());
+        assert_eq!(9, merged.len());
+        assert!(merged.contains(4) && !merged.contains(8) && !merged.contains(13));
+        merged.insert(Interval::new(7, 9));
+        assert_eq!(set(&[(1, 12)]), merged);
+        merged.remove(Interval::new(3, 4));
+        merged.remove(Interval::new(12, 20));
+        assert_eq!(set(&[(1, 2), (5, 11)]), merged);
+        assert!(IntervalSet::<u8>::new().is_empty());
+    }
+
+    #[test]
+    fn interval_tree_queries() {
+        let intervals = [(5, 9), (1, 3), (2, 6), (8, 8), (12, 15), (7, 4), (0, 20), (3, 3)];
+        let tree = intervals.iter().map(|&(start, end)| (Interval::new(start, end), (start, end)));
+        let tree = tree.collect::<IntervalTree<i32, _>>();
+        assert_eq!(7, tree.len());
+        for start in -1..=21 {
+            for end in start..=21 {
+                let query = Interval::new(start, end);
+                let found =
+                    tree.overlapping(query).into_iter().map(|(_, v)| *v).collect::<Vec<_>>();
+                let expected = intervals.iter().copied();
+                let expected = expected.filter(|&(s, e)| s <= e && s <= end && start <= e);
+                let mut expected = expected.collect::<Vec<_>>();
+                expected.sort_unstable();
+                assert_eq!(expected, found, "query: {start}-{end}");
+            }
+        }
+        let stabbed = tree.stabbing(3).into_iter().map(|(_, v)| *v).collect::<Vec<_>>();
+        assert_eq!(vec![(0, 20), (1, 3), (2, 6), (3, 3)], stabbed);
+        assert_eq!(Some((3, 4)), tree.max_overlap());
+        assert_eq!(None, IntervalTree::<u8, ()>::new(Vec::new()).max_overlap());
+    }
+}
@@ -2,6 +2,7 @@
 #![feature(iterator_try_collect)]
 #![feature(get_many_mut)]
 #![feature(array_windows)]
+#![feature(once_cell)]
 
 use std::{
     env,
@@ -201,3 +202,4 @@ pub mod day03;
 pub mod day04;
 pub mod day05;
 pub mod day06;
+pub mod interval;